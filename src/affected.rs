@@ -0,0 +1,101 @@
+use globset::GlobSet;
+use petgraph::Direction;
+use std::{collections::HashSet, path::PathBuf};
+
+use crate::{dependency_graph::DependencyGraph, module::ModuleKind};
+
+pub struct AffectedFiles {
+    /// Changed paths which are neither in the graph nor imported by anything in the graph, eg documentation
+    pub unmatched_changes: Vec<PathBuf>,
+    /// Every file which is changed or transitively depends on a change, excluding tests
+    pub affected_files: Vec<PathBuf>,
+    /// The affected files which match the test globs
+    pub affected_tests: Vec<PathBuf>,
+}
+
+/// Finds every file which may be affected by changes to the given paths.
+/// Paths which no longer exist (ie deleted files or the old side of a rename) affect the files which imported them.
+pub fn get_affected_files<'a>(
+    graph: &'a DependencyGraph,
+    changed_paths: &[PathBuf],
+    tests: &GlobSet,
+) -> Result<AffectedFiles, &'a str> {
+    let mut unmatched_changes = vec![];
+    let mut roots = vec![];
+    for path in changed_paths {
+        if let Some(module_id) = graph.get_module_id_for_path(path) {
+            if graph.get_module_kind(module_id) == ModuleKind::File {
+                roots.push(module_id);
+                continue;
+            }
+        }
+
+        let importers = graph.get_importers_of_missing_path(path)?;
+        if importers.is_empty() {
+            unmatched_changes.push(path.clone());
+        }
+        roots.extend(importers);
+    }
+
+    let (mut affected_tests, mut affected_files): (Vec<_>, Vec<_>) = graph
+        .get_reachable_module_ids(&roots, Direction::Incoming)?
+        .into_iter()
+        .filter(|module_id| graph.get_module_kind(*module_id) == ModuleKind::File)
+        .map(|module_id| graph.get_path_for_module_id(module_id))
+        .partition(|path| tests.is_match(path));
+    affected_files.sort();
+    affected_tests.sort();
+
+    return Ok(AffectedFiles {
+        unmatched_changes,
+        affected_files,
+        affected_tests,
+    });
+}
+
+pub struct TestSelection {
+    pub unmatched_changes: Vec<PathBuf>,
+    /// The tests which are changed or transitively depend on a change
+    pub tests: Vec<PathBuf>,
+    /// Changed files which are not tests and which no test depends on
+    pub uncovered_changes: Vec<PathBuf>,
+}
+
+/// Finds the tests which may be affected by changes to the given paths, and the changes that no test covers
+pub fn get_tests_for_changes<'a>(
+    graph: &'a DependencyGraph,
+    changed_paths: &[PathBuf],
+    tests: &GlobSet,
+) -> Result<TestSelection, &'a str> {
+    let affected_files = get_affected_files(graph, changed_paths, tests)?;
+
+    let changed_files = changed_paths
+        .iter()
+        .filter(|path| !tests.is_match(path))
+        .filter(|path| {
+            return graph
+                .get_module_id_for_path(path)
+                .is_some_and(|module_id| graph.get_module_kind(module_id) == ModuleKind::File);
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    let roots_by_dependent =
+        graph.get_roots_reaching_dependencies(&changed_files, Direction::Incoming, 0)?;
+    let covered_changes = affected_files
+        .affected_tests
+        .iter()
+        .filter_map(|test| roots_by_dependent.get(test))
+        .flatten()
+        .collect::<HashSet<_>>();
+    let uncovered_changes = changed_files
+        .iter()
+        .filter(|path| !covered_changes.contains(path))
+        .cloned()
+        .collect();
+
+    return Ok(TestSelection {
+        unmatched_changes: affected_files.unmatched_changes,
+        tests: affected_files.affected_tests,
+        uncovered_changes,
+    });
+}
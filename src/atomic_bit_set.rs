@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const BITS_PER_WORD: usize = u64::BITS as usize;
+
+/// A fixed-size set of indices which can be shared between threads without a lock
+pub struct AtomicBitSet {
+    words: Vec<AtomicU64>,
+}
+
+impl AtomicBitSet {
+    pub fn new(len: usize) -> Self {
+        return Self {
+            words: (0..len.div_ceil(BITS_PER_WORD))
+                .map(|_| AtomicU64::new(0))
+                .collect(),
+        };
+    }
+
+    /// Sets the bit for the index, returning whether it was already set.
+    /// Only one of any number of concurrent callers for the same index will see `false`.
+    pub fn test_and_set(&self, idx: usize) -> bool {
+        let mask = 1 << (idx % BITS_PER_WORD);
+        let previous = self.words[idx / BITS_PER_WORD].fetch_or(mask, Ordering::Relaxed);
+        return previous & mask != 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rayon::prelude::*;
+
+    use super::AtomicBitSet;
+
+    #[test]
+    fn test_and_set_reports_previous_value() {
+        let set = AtomicBitSet::new(130);
+        for idx in [0, 63, 64, 129] {
+            assert!(!set.test_and_set(idx));
+            assert!(set.test_and_set(idx));
+        }
+        assert!(!set.test_and_set(1));
+    }
+
+    #[test]
+    fn only_one_concurrent_caller_claims_each_index() {
+        let len = 10_000;
+        let set = AtomicBitSet::new(len);
+        let claims: Vec<AtomicUsize> = (0..len).map(|_| AtomicUsize::new(0)).collect();
+
+        // every index is raced by many tasks, and neighbouring indices share words
+        (0..len * 16).into_par_iter().for_each(|task| {
+            let idx = (task * 7919) % len;
+            if !set.test_and_set(idx) {
+                claims[idx].fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        assert!(claims
+            .iter()
+            .all(|count| count.load(Ordering::Relaxed) == 1));
+    }
+}
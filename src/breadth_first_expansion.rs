@@ -0,0 +1,79 @@
+use petgraph::{graph::NodeIndex, Direction};
+use std::collections::VecDeque;
+
+use crate::module::ModuleGraph;
+
+pub struct VisitedNode {
+    pub node_idx: NodeIndex,
+    /// The minimum number of imports between a root and this node
+    pub depth: u32,
+    /// The node this node was first reached from - `None` for the roots
+    pub parent: Option<NodeIndex>,
+}
+
+/// A traversal which visits nodes in order of their distance from the roots.
+/// Unlike `DepthFirstExpansion` this guarantees that each node is reported with its minimum depth, at the cost of not
+/// being splittable for parallel iteration.
+pub struct BreadthFirstExpansion<'a> {
+    direction: Direction,
+    graph: &'a ModuleGraph,
+    max_depth: u32,
+    queue: VecDeque<VisitedNode>,
+    seen_nodes: Vec<bool>,
+}
+
+impl<'a> BreadthFirstExpansion<'a> {
+    /// Create a new search with the given starting points.
+    pub fn new(
+        graph: &'a ModuleGraph,
+        direction: Direction,
+        max_depth: u32,
+        roots: Vec<NodeIndex>,
+    ) -> Self {
+        let mut seen_nodes = vec![false; graph.node_count()];
+        let mut queue = VecDeque::with_capacity(roots.len());
+        for node_idx in roots {
+            if !seen_nodes[node_idx.index()] {
+                seen_nodes[node_idx.index()] = true;
+                queue.push_back(VisitedNode {
+                    node_idx,
+                    depth: 0,
+                    parent: None,
+                });
+            }
+        }
+
+        return Self {
+            direction,
+            graph,
+            max_depth,
+            queue,
+            seen_nodes,
+        };
+    }
+}
+
+impl<'a> Iterator for BreadthFirstExpansion<'a> {
+    type Item = VisitedNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.queue.pop_front()?;
+
+        if self.max_depth == 0 || item.depth < self.max_depth {
+            let new_depth = item.depth + 1;
+            for neighbor in self.graph.neighbors_directed(item.node_idx, self.direction) {
+                // nodes are marked when queued rather than when visited so that each node is only ever queued once
+                if !self.seen_nodes[neighbor.index()] {
+                    self.seen_nodes[neighbor.index()] = true;
+                    self.queue.push_back(VisitedNode {
+                        node_idx: neighbor,
+                        depth: new_depth,
+                        parent: Some(item.node_idx),
+                    });
+                }
+            }
+        }
+
+        return Some(item);
+    }
+}
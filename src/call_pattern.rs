@@ -0,0 +1,73 @@
+use std::str::FromStr;
+
+/// Describes a call expression which references a module by name - eg `jest.mock('../api')`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallPattern {
+    /// The dot-separated path of the callee - eg `jest.mock` or `import.meta.resolve`
+    pub callee: String,
+    /// The index of the argument which contains the module name
+    pub arg_index: usize,
+}
+impl CallPattern {
+    fn new(callee: &str, arg_index: usize) -> Self {
+        return Self {
+            callee: callee.to_string(),
+            arg_index,
+        };
+    }
+}
+
+// Allows patterns to be passed on the CLI in the form `callee` or `callee:arg_index` - eg `jest.mock` or `loadModule:1`
+impl FromStr for CallPattern {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (callee, arg_index) = match value.rsplit_once(':') {
+            Some((callee, arg_index)) => (
+                callee,
+                arg_index
+                    .parse::<usize>()
+                    .map_err(|e| format!("Invalid argument index \"{}\" - {}", arg_index, e))?,
+            ),
+            None => (value, 0),
+        };
+
+        if callee.is_empty() || callee.split('.').any(|part| part.is_empty()) {
+            return Err(format!("Invalid callee \"{}\"", callee));
+        }
+
+        return Ok(Self::new(callee, arg_index));
+    }
+}
+
+pub fn jest_call_patterns() -> Vec<CallPattern> {
+    return vec![
+        CallPattern::new("jest.createMockFromModule", 0),
+        CallPattern::new("jest.doMock", 0),
+        CallPattern::new("jest.dontMock", 0),
+        CallPattern::new("jest.genMockFromModule", 0),
+        CallPattern::new("jest.mock", 0),
+        CallPattern::new("jest.requireActual", 0),
+        CallPattern::new("jest.requireMock", 0),
+        CallPattern::new("jest.setMock", 0),
+        CallPattern::new("jest.unmock", 0),
+    ];
+}
+
+pub fn vitest_call_patterns() -> Vec<CallPattern> {
+    return vec![
+        CallPattern::new("vi.doMock", 0),
+        CallPattern::new("vi.doUnmock", 0),
+        CallPattern::new("vi.importActual", 0),
+        CallPattern::new("vi.importMock", 0),
+        CallPattern::new("vi.mock", 0),
+        CallPattern::new("vi.unmock", 0),
+    ];
+}
+
+pub fn node_call_patterns() -> Vec<CallPattern> {
+    return vec![
+        CallPattern::new("import.meta.resolve", 0),
+        CallPattern::new("require.resolve", 0),
+    ];
+}
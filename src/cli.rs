@@ -1,8 +1,14 @@
-use clap::{builder::ValueParser, Parser, ValueEnum};
+use clap::{builder::ValueParser, Parser, Subcommand, ValueEnum};
 use petgraph::Direction;
 use std::path::PathBuf;
 
-use crate::file_system::path_parser_absolute;
+use crate::{
+    call_pattern::{jest_call_patterns, node_call_patterns, vitest_call_patterns, CallPattern},
+    collapse::Grouping,
+    file_system::{build_glob_set, path_parser_absolute, path_parser_module},
+    graph_export::ExportFormat,
+    output::OutputFormat,
+};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum CliDirection {
@@ -20,32 +26,430 @@ impl From<CliDirection> for Direction {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum CliView {
+    /// Nest each module under the module it was first reached from
+    Tree,
+    /// Group the modules by the minimum number of imports needed to reach them
+    Layers,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum CliOutputFormat {
+    /// Human-readable output
+    Text,
+    /// A single JSON document
+    Json,
+    /// One JSON document per line
+    Ndjson,
+    /// SARIF 2.1.0, for code scanning tools
+    Sarif,
+}
+impl From<CliOutputFormat> for OutputFormat {
+    fn from(value: CliOutputFormat) -> Self {
+        return match value {
+            CliOutputFormat::Text => Self::Text,
+            CliOutputFormat::Json => Self::Json,
+            CliOutputFormat::Ndjson => Self::Ndjson,
+            CliOutputFormat::Sarif => Self::Sarif,
+        };
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum CliCallPatternPreset {
+    /// `jest.mock`, `jest.requireActual`, etc
+    Jest,
+    /// `vi.mock`, `vi.importActual`, etc
+    Vitest,
+    /// `require.resolve` and `import.meta.resolve`
+    Node,
+}
+impl From<CliCallPatternPreset> for Vec<CallPattern> {
+    fn from(value: CliCallPatternPreset) -> Self {
+        return match value {
+            CliCallPatternPreset::Jest => jest_call_patterns(),
+            CliCallPatternPreset::Vitest => vitest_call_patterns(),
+            CliCallPatternPreset::Node => node_call_patterns(),
+        };
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum CliTestListFormat {
+    /// One absolute path per line
+    Lines,
+    /// Space-separated regex patterns for `jest <patterns>`, quoted for the shell or xargs
+    Jest,
+    /// Space-separated filters for `vitest run <filters>`, quoted for the shell or xargs
+    Vitest,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum CliMetricsSort {
+    FanIn,
+    FanOut,
+    TransitiveDependencies,
+    TransitiveDependents,
+    Instability,
+    Depth,
+    TransitiveBytes,
+    Path,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum CliExportFormat {
+    /// Graphviz DOT
+    Dot,
+    /// A Mermaid flowchart for markdown docs and PR comments
+    Mermaid,
+    /// GraphML, for yEd, Cytoscape, etc
+    Graphml,
+    /// GEXF, for Gephi
+    Gexf,
+}
+impl From<CliExportFormat> for ExportFormat {
+    fn from(value: CliExportFormat) -> Self {
+        return match value {
+            CliExportFormat::Dot => Self::Dot,
+            CliExportFormat::Mermaid => Self::Mermaid,
+            CliExportFormat::Graphml => Self::GraphMl,
+            CliExportFormat::Gexf => Self::Gexf,
+        };
+    }
+}
+
+const DEFAULT_TEST_GLOBS: [&str; 2] = ["**/*.{test,spec}.*", "**/__tests__/**"];
+
+#[derive(Subcommand, Debug)]
+pub enum CliCommand {
+    /// Report the exports which are not imported by any other module
+    UnusedExports {
+        /// Globs for entry point files whose exports are public API, eg `src/index.ts`
+        #[arg(long, short = 'e')]
+        entry: Vec<String>,
+    },
+    /// Report the files which cannot be reached from any entry point
+    DeadFiles {
+        /// Globs for entry point files, eg `src/pages/**/*.tsx` or `**/*.test.ts`
+        #[arg(long, short = 'e')]
+        entry: Vec<String>,
+
+        /// Also use the `main`, `module`, `bin` and `exports` of every package.json in the search paths as entry points
+        #[arg(long)]
+        package_entry_points: bool,
+    },
+    /// Report the import cycles, largest first
+    Cycles {
+        /// Ignore type-only imports as they are erased at runtime and cannot cause runtime issues
+        #[arg(long)]
+        ignore_type_only: bool,
+
+        /// List every module in each cycle, not just the shortest loop
+        #[arg(long)]
+        members: bool,
+    },
+    /// Report every file which transitively depends on a set of changed files
+    Affected {
+        /// A git revision range to diff, eg `main...HEAD`.
+        /// If omitted, the output of `git diff --name-only` or `git diff --name-status`, optionally with `-z`, is read from
+        /// stdin
+        range: Option<String>,
+
+        /// Globs for test files, which are reported separately
+        #[arg(long, short = 't', default_values = DEFAULT_TEST_GLOBS)]
+        test: Vec<String>,
+    },
+    /// List the tests which transitively depend on a set of changed files
+    TestsFor {
+        /// A git revision range to diff, eg `main...HEAD`.
+        /// If omitted, the output of `git diff --name-only` or `git diff --name-status`, optionally with `-z`, is read from
+        /// stdin
+        range: Option<String>,
+
+        /// Globs for test files
+        #[arg(long, short = 't', default_values = DEFAULT_TEST_GLOBS)]
+        test: Vec<String>,
+
+        /// How to print the list of tests
+        #[arg(value_enum, long, default_value_t = CliTestListFormat::Lines)]
+        format: CliTestListFormat,
+    },
+    /// Save the graph so that it can be compared against later with `diff`
+    Snapshot {
+        /// The file to write the snapshot to
+        #[arg(long, short = 'o')]
+        output: PathBuf,
+    },
+    /// Save the built graph in a binary format, so that other runs can load it with `--graph` instead of parsing and
+    /// resolving the search paths
+    SaveGraph {
+        /// The file to write the graph to
+        #[arg(long, short = 'o')]
+        output: PathBuf,
+    },
+    /// Compare a saved snapshot of the graph against the current graph or another snapshot
+    Diff {
+        /// The snapshot to compare against, eg one taken on the base branch
+        #[arg(long, value_parser = ValueParser::new(path_parser_absolute))]
+        base: PathBuf,
+
+        /// A snapshot to use instead of the current graph
+        #[arg(long, value_parser = ValueParser::new(path_parser_absolute))]
+        head: Option<PathBuf>,
+
+        /// Report modules whose transitive dependency count grew by more than this many modules
+        #[arg(long, default_value_t = 10)]
+        growth_threshold: usize,
+
+        /// Ignore type-only imports when looking for new cycles
+        #[arg(long)]
+        ignore_type_only: bool,
+    },
+    /// Check every import against the boundary rules in a config file, exiting non-zero if any are violated
+    CheckRules {
+        /// The JSON rules config, which may contain comments
+        #[arg(long, short = 'c', default_value = "athena.rules.json", value_parser = ValueParser::new(path_parser_absolute))]
+        config: PathBuf,
+    },
+    /// Report imports which reach into the internals of another package in the search paths instead of using one of
+    /// its public entry points, exiting non-zero if there are any
+    PublicApi,
+    /// Report packages which are imported but not declared in the nearest package.json, declared dependencies which
+    /// are never imported, and dev dependencies used at runtime by a package's entry points, exiting non-zero if
+    /// there are any
+    CheckDependencies,
+    /// Report the fan-in, fan-out, transitive size and stability of every file
+    Metrics {
+        /// Globs for entry point files to measure depth from, eg `src/index.ts`
+        #[arg(long, short = 'e')]
+        entry: Vec<String>,
+
+        /// The metric to sort by - numbers are sorted largest first
+        #[arg(value_enum, long, default_value_t = CliMetricsSort::TransitiveDependents)]
+        sort: CliMetricsSort,
+
+        /// Only list this many files
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Write the graph in a format for visualization tools.
+    /// If `--file` or `--files-from` is given, only the modules reached from those files are written
+    Export {
+        #[arg(value_enum, long, default_value_t = CliExportFormat::Dot)]
+        format: CliExportFormat,
+
+        /// The file to write to - defaults to stdout
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+    /// Write a self-contained HTML page for exploring the graph in a browser
+    Report {
+        /// The HTML file to write
+        #[arg(long)]
+        html: PathBuf,
+    },
+    /// Build the graph, then keep it up to date as files in the search paths are created, changed and deleted.
+    /// The `--file` query, and the cycles if requested, are printed again whenever their results change
+    Watch {
+        /// Also print the import cycles whenever they change
+        #[arg(long)]
+        cycles: bool,
+
+        /// Ignore type-only imports when finding cycles
+        #[arg(long, requires = "cycles")]
+        ignore_type_only: bool,
+    },
+    /// Show the import chains which cause one file to depend on another
+    Path {
+        /// The file which has the dependency
+        #[arg(long, value_parser = ValueParser::new(path_parser_absolute))]
+        from: PathBuf,
+
+        /// The file which is depended upon
+        #[arg(long, value_parser = ValueParser::new(path_parser_absolute))]
+        to: PathBuf,
+
+        /// The number of shortest paths to list
+        #[arg(short = 'k', default_value_t = 1, value_parser = ValueParser::new(parse_positive_count))]
+        k: usize,
+
+        /// List every path with at most this many imports instead of the k shortest
+        #[arg(long, conflicts_with = "k", value_parser = ValueParser::new(parse_positive_count))]
+        max_length: Option<usize>,
+    },
+}
+
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_precedence_over_arg = true)]
 pub struct CliArgs {
     /// The paths to search for files
-    #[arg(required = true, num_args = 1.., value_parser = ValueParser::new(path_parser_absolute))]
+    #[arg(required_unless_present = "graph", num_args = 1.., value_parser = ValueParser::new(path_parser_absolute))]
     pub search_paths: Vec<PathBuf>,
 
     /// The path to a tsconfig file to resolve `paths` and `baseUrl` from
-    #[arg(long, short = 'p', required = true, value_parser = ValueParser::new(path_parser_absolute))]
-    pub tsconfig_path: PathBuf,
+    #[arg(long, short = 'p', required_unless_present = "graph", value_parser = ValueParser::new(path_parser_absolute))]
+    pub tsconfig_path: Option<PathBuf>,
+
+    /// Load a graph written by `save-graph` instead of parsing and resolving the search paths.
+    /// Paths in the graph are absolute, so it must be loaded from the same location it was saved in
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["search_paths", "tsconfig_path", "cache_dir", "dump_resolved_imports"],
+        value_parser = ValueParser::new(path_parser_absolute),
+    )]
+    pub graph: Option<PathBuf>,
 
-    /// The file to analyze dependencies for
-    #[arg(long, short = 'f', value_parser = ValueParser::new(path_parser_absolute))]
-    pub file: Option<PathBuf>,
+    /// The file to analyze dependencies for - repeat to traverse from many files at once.
+    /// With a collapsed graph this can also be a group, eg `NAME` for `--collapse-group NAME=GLOB`, and a file is
+    /// replaced by its group.
+    #[arg(long, short = 'f', value_parser = ValueParser::new(path_parser_module))]
+    pub file: Vec<PathBuf>,
+
+    /// Read additional files to analyze from a newline-separated list, or from stdin if `-`
+    #[arg(long, value_name = "PATH")]
+    pub files_from: Option<PathBuf>,
+
+    /// Also list which of the given files reach each dependency
+    #[arg(long)]
+    pub attribute_roots: bool,
 
     /// The direction to get dependencies for the given file
     #[arg(value_enum, long, short = 'd', default_value_t = CliDirection::Dependencies)]
     pub direction: CliDirection,
 
+    /// Instead of expanding dependencies, list the modules which directly import this export name from the given file
+    #[arg(long, requires = "file")]
+    pub export_name: Option<String>,
+
     /// The maximum depth to expand dependencies along
     #[arg(long, short = 'm', default_value_t = 0)]
     pub max_depth: u32,
 
+    /// How to display the dependencies of the given file
+    #[arg(value_enum, long, default_value_t = CliView::Tree)]
+    pub view: CliView,
+
+    /// Collapse the files in each directory this many levels below the current directory into a single module -
+    /// eg with `2`, `src/components/button/index.ts` becomes part of `src/components`
+    #[arg(long, value_name = "DEPTH", conflicts_with_all = ["collapse_packages", "collapse_group"])]
+    pub collapse_directories: Option<usize>,
+
+    /// Collapse the files of each package.json in the search paths into a single module
+    #[arg(long, conflicts_with = "collapse_group")]
+    pub collapse_packages: bool,
+
+    /// Collapse the files matching a glob into a single module with the given name - repeat for more groups.
+    /// Files are placed in the group of the first glob they match
+    #[arg(long, value_name = "NAME=GLOB", value_parser = ValueParser::new(parse_collapse_group))]
+    pub collapse_group: Vec<(String, String)>,
+
+    /// How to print the results - the JSON, NDJSON and SARIF output is documented in the README
+    #[arg(value_enum, long, default_value_t = CliOutputFormat::Text)]
+    pub format: CliOutputFormat,
+
+    /// Print file paths relative to the git repository root instead of absolute, in JSON and NDJSON output.
+    /// Outside of a repository they are relative to the current directory
+    #[arg(long)]
+    pub relative_paths: bool,
+
+    /// Don't print progress and timing information to stderr
+    #[arg(long, short = 'q')]
+    pub quiet: bool,
+
+    /// Cache the parsed and resolved imports of each file in this directory, so that later runs only parse the files
+    /// which have changed. Imports are only resolved again if a file is added or removed, or the tsconfig or a
+    /// package.json changes
+    #[arg(long, value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
     /// Dump the {file path -> imported name} list to a file for debug purposes
     #[arg(long)]
     pub dump_resolved_imports: Option<PathBuf>,
+
+    /// Additional calls which reference a module, in the form `callee` or `callee:argument_index`
+    /// eg `loadModule` or `registry.lazy:1`
+    #[arg(long, value_name = "CALLEE[:ARG_INDEX]")]
+    pub call_pattern: Vec<CallPattern>,
+
+    /// The built-in sets of calls which reference a module
+    #[arg(value_enum, long, value_delimiter = ',', default_values_t = [CliCallPatternPreset::Jest, CliCallPatternPreset::Vitest, CliCallPatternPreset::Node])]
+    pub call_pattern_presets: Vec<CliCallPatternPreset>,
+
+    /// Run an analysis instead of querying the dependencies of a file
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+impl CliArgs {
+    /// Combines the presets and the user-supplied patterns into the final list of call patterns
+    pub fn get_call_patterns(&self) -> Vec<CallPattern> {
+        let mut call_patterns = self
+            .call_pattern_presets
+            .iter()
+            .flat_map(|preset| Vec::<CallPattern>::from(*preset))
+            .collect::<Vec<_>>();
+        call_patterns.extend(self.call_pattern.iter().cloned());
+
+        return call_patterns;
+    }
+
+    /// Whether the command reads the package.jsons in the search paths. The resolver also needs them for bare imports,
+    /// which can only be checked once the files are parsed.
+    pub fn needs_package_jsons(&self) -> bool {
+        if self.collapse_packages {
+            return true;
+        }
+        return match &self.command {
+            Some(CliCommand::DeadFiles {
+                package_entry_points,
+                ..
+            }) => *package_entry_points,
+            // a saved graph or a watched one may be used for any command later
+            Some(
+                CliCommand::PublicApi
+                | CliCommand::CheckDependencies
+                | CliCommand::Export { .. }
+                | CliCommand::Report { .. }
+                | CliCommand::SaveGraph { .. }
+                | CliCommand::Watch { .. },
+            ) => true,
+            _ => false,
+        };
+    }
+
+    /// The grouping to collapse the graph with, if any was requested
+    pub fn get_grouping(&self) -> Result<Option<Grouping>, globset::Error> {
+        if let Some(depth) = self.collapse_directories {
+            return Ok(Some(Grouping::Directory(depth)));
+        }
+        if self.collapse_packages {
+            return Ok(Some(Grouping::Package));
+        }
+        if self.collapse_group.is_empty() {
+            return Ok(None);
+        }
+
+        let (names, globs): (Vec<String>, Vec<String>) =
+            self.collapse_group.iter().cloned().unzip();
+        return Ok(Some(Grouping::Globs(build_glob_set(&globs)?, names)));
+    }
+}
+
+fn parse_collapse_group(value: &str) -> Result<(String, String), String> {
+    return match value.split_once('=') {
+        Some((name, glob)) if !name.is_empty() && !glob.is_empty() => {
+            Ok((name.to_string(), glob.to_string()))
+        }
+        _ => Err(format!("Expected NAME=GLOB, got {}", value)),
+    };
+}
+
+fn parse_positive_count(value: &str) -> Result<usize, String> {
+    return match value.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("Expected a number greater than 0, got {}", value)),
+    };
 }
 
 pub fn parse_cli() -> CliArgs {
@@ -0,0 +1,160 @@
+use globset::GlobSet;
+use petgraph::visit::EdgeRef;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    import_visitor::{ExportBinding, ParsedModule},
+    module::{EdgeWeight, ModuleId, ModuleKind},
+};
+
+/// How to assign files to the groups of a collapsed graph.
+/// Node modules are never grouped as each package is already a single module.
+pub enum Grouping {
+    /// Group files by their ancestor directory this many levels below the current directory
+    Directory(usize),
+    /// Group files by the package.json they belong to - files outside of any package are left as-is
+    Package,
+    /// Group files by the first glob they match, with one group name per glob - unmatched files are left as-is
+    Globs(GlobSet, Vec<String>),
+}
+impl Grouping {
+    fn get_group(
+        &self,
+        graph: &DependencyGraph,
+        module_id: ModuleId,
+        path: &Path,
+        current_dir: &Path,
+    ) -> PathBuf {
+        return match self {
+            Grouping::Directory(depth) => {
+                let directory = path.parent().expect("Path should not be the root");
+                let (base, relative) = match directory.strip_prefix(current_dir) {
+                    Ok(relative) => (current_dir.to_path_buf(), relative),
+                    Err(_) => (PathBuf::from("/"), directory),
+                };
+                let mut group = base;
+                group.extend(
+                    relative
+                        .components()
+                        .filter(|component| matches!(component, Component::Normal(_)))
+                        .take(*depth),
+                );
+                group
+            }
+            Grouping::Package => match graph.get_package_json_index_for_module_id(module_id) {
+                Some(index) => graph.get_package_jsons()[index].directory().to_path_buf(),
+                None => path.to_path_buf(),
+            },
+            Grouping::Globs(glob_set, names) => match glob_set.matches(path).first() {
+                Some(glob_index) => current_dir.join(&names[*glob_index]),
+                None => path.to_path_buf(),
+            },
+        };
+    }
+}
+
+/// Builds the quotient graph of the given graph, where each group of files is a single module.
+/// Every import between two groups is kept as its own edge, so the number of edges between two groups is the number
+/// of file imports between them. Imports within a group are dropped so that a group does not depend on itself.
+/// Each group's parsed module is the concatenation of its files', so import locations still point into the files.
+pub fn collapse_graph<'a>(
+    graph: &'a DependencyGraph,
+    grouping: &Grouping,
+) -> Result<DependencyGraph, &'a str> {
+    let module_graph = graph.get_graph()?;
+    let current_dir = std::env::current_dir().expect("Unable to read the current directory");
+
+    // sorted so that the concatenated parsed modules are stable between runs
+    let mut files_by_group: BTreeMap<PathBuf, Vec<(PathBuf, ModuleId)>> = BTreeMap::new();
+    let mut group_for_module: HashMap<ModuleId, PathBuf> = HashMap::new();
+    for module_id in graph.get_module_ids() {
+        if graph.get_module_kind(module_id) != ModuleKind::File {
+            continue;
+        }
+        let path = graph.get_path_for_module_id(module_id);
+        let group = grouping.get_group(graph, module_id, &path, &current_dir);
+        files_by_group
+            .entry(group.clone())
+            .or_default()
+            .push((path, module_id));
+        group_for_module.insert(module_id, group);
+    }
+    for files in files_by_group.values_mut() {
+        files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+
+    // the offset of each file's dependencies within its group's concatenated dependencies
+    let mut dependency_offsets: HashMap<ModuleId, u32> = HashMap::new();
+    let mut group_parsed_modules = Vec::with_capacity(files_by_group.len());
+    for (group, files) in files_by_group.iter() {
+        let mut group_parsed_module = ParsedModule::default();
+        for (_, module_id) in files.iter() {
+            let dependency_offset = group_parsed_module.dependencies.len();
+            dependency_offsets.insert(*module_id, dependency_offset as u32);
+            if let Some(parsed_module) = graph.get_parsed_module(*module_id) {
+                group_parsed_module
+                    .dependencies
+                    .extend(parsed_module.dependencies.iter().cloned());
+                // re-exports point at a dependency by index, so they move with the dependencies
+                group_parsed_module
+                    .exports
+                    .extend(parsed_module.exports.iter().map(|export| {
+                        let mut export = export.clone();
+                        if let ExportBinding::ReExport {
+                            dependency_index, ..
+                        }
+                        | ExportBinding::ReExportAll {
+                            dependency_index, ..
+                        } = &mut export
+                        {
+                            *dependency_index += dependency_offset;
+                        }
+                        return export;
+                    }));
+            }
+        }
+        group_parsed_modules.push((group, group_parsed_module));
+    }
+
+    let mut imports = Vec::with_capacity(module_graph.edge_count());
+    for edge in module_graph.edge_references() {
+        let from_module_id = module_graph[edge.source()];
+        let to_module_id = module_graph[edge.target()];
+        let from_group = &group_for_module[&from_module_id];
+        let (to_path, to_kind) = match group_for_module.get(&to_module_id) {
+            Some(to_group) if to_group == from_group => continue,
+            Some(to_group) => (to_group.clone(), ModuleKind::File),
+            None => (
+                graph.get_path_for_module_id(to_module_id),
+                ModuleKind::NodeModule,
+            ),
+        };
+        imports.push((
+            from_group.clone(),
+            to_path,
+            to_kind,
+            EdgeWeight {
+                dependency_index: dependency_offsets[&from_module_id]
+                    + edge.weight().dependency_index,
+                ..*edge.weight()
+            },
+        ));
+    }
+
+    let group_paths = files_by_group.keys().cloned().collect::<Vec<_>>();
+    let group_for_file = files_by_group
+        .iter()
+        .flat_map(|(group, files)| files.iter().map(|(path, _)| (path.clone(), group.clone())))
+        .collect();
+    return Ok(DependencyGraph::from_resolved_imports(
+        &group_paths,
+        group_parsed_modules,
+        imports,
+        graph.get_package_jsons().to_vec(),
+        group_for_file,
+    ));
+}
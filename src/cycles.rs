@@ -0,0 +1,205 @@
+use petgraph::{
+    algo::tarjan_scc,
+    graph::NodeIndex,
+    visit::{EdgeFiltered, IntoNeighbors},
+};
+use rayon::prelude::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+};
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    module::{ModuleGraph, ModuleId},
+};
+
+#[derive(PartialEq)]
+pub struct Cycle {
+    /// Every module in the strongly connected component
+    pub modules: Vec<PathBuf>,
+    /// The shortest import chain within the component which forms a loop - the first and last module are the same
+    pub shortest_loop: Vec<PathBuf>,
+}
+
+/// Finds every import cycle in the graph, largest first
+pub fn get_cycles(graph: &DependencyGraph, ignore_type_only: bool) -> Result<Vec<Cycle>, &str> {
+    let module_graph = graph.get_graph()?;
+    let to_paths = |module_ids: &[ModuleId]| -> Vec<PathBuf> {
+        return module_ids
+            .iter()
+            .map(|module_id| graph.get_path_for_module_id(*module_id))
+            .collect();
+    };
+
+    let mut cycles = find_cycles(module_graph, ignore_type_only)
+        .iter()
+        .map(|(modules, shortest_loop)| {
+            let mut modules = to_paths(modules);
+            modules.sort();
+            return Cycle {
+                modules,
+                shortest_loop: to_paths(shortest_loop),
+            };
+        })
+        .collect::<Vec<_>>();
+    cycles.sort_by(|a, b| {
+        b.modules
+            .len()
+            .cmp(&a.modules.len())
+            .then_with(|| a.modules.cmp(&b.modules))
+    });
+
+    return Ok(cycles);
+}
+
+/// Finds the strongly connected components which contain a cycle, along with the shortest loop in each component
+pub fn find_cycles(
+    module_graph: &ModuleGraph,
+    ignore_type_only: bool,
+) -> Vec<(Vec<ModuleId>, Vec<ModuleId>)> {
+    let filtered_graph = EdgeFiltered::from_fn(module_graph, |edge| {
+        return !ignore_type_only || !edge.weight().is_type_only;
+    });
+
+    let components = tarjan_scc(&filtered_graph)
+        .into_iter()
+        .filter(|component| {
+            // a lone module is only a cycle if it imports itself
+            return component.len() > 1
+                || filtered_graph
+                    .neighbors(component[0])
+                    .any(|neighbor| neighbor == component[0]);
+        })
+        .collect::<Vec<_>>();
+
+    let mut component_for_node: Vec<Option<usize>> = vec![None; module_graph.node_count()];
+    for (component_idx, component) in components.iter().enumerate() {
+        for node_idx in component {
+            component_for_node[node_idx.index()] = Some(component_idx);
+        }
+    }
+
+    return components
+        .par_iter()
+        .enumerate()
+        .map(|(component_idx, component)| {
+            let shortest_loop = find_shortest_loop(&filtered_graph, component, |node_idx| {
+                return component_for_node[node_idx.index()] == Some(component_idx);
+            });
+            return (
+                component
+                    .iter()
+                    .map(|node_idx| module_graph[*node_idx])
+                    .collect(),
+                shortest_loop
+                    .iter()
+                    .map(|node_idx| module_graph[*node_idx])
+                    .collect(),
+            );
+        })
+        .collect();
+}
+
+/// Runs a BFS from each module in the component back to itself, keeping the shortest loop found.
+/// Each search is bounded by the best loop found so far, so large components with short loops stay cheap.
+fn find_shortest_loop<G>(
+    graph: G,
+    component: &[NodeIndex],
+    is_in_component: impl Fn(NodeIndex) -> bool,
+) -> Vec<NodeIndex>
+where
+    G: IntoNeighbors<NodeId = NodeIndex>,
+{
+    let mut best_loop: Vec<NodeIndex> = vec![];
+
+    for start in component.iter().copied() {
+        let mut parents: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut queue: VecDeque<(NodeIndex, usize)> = VecDeque::from([(start, 0)]);
+
+        'search: while let Some((node_idx, depth)) = queue.pop_front() {
+            // a loop found from here would be at least `depth + 1` edges long
+            if !best_loop.is_empty() && depth + 1 >= best_loop.len() - 1 {
+                break;
+            }
+
+            for neighbor in graph.neighbors(node_idx) {
+                if neighbor == start {
+                    let mut new_loop = vec![start];
+                    let mut current = node_idx;
+                    while current != start {
+                        new_loop.push(current);
+                        current = parents[&current];
+                    }
+                    new_loop.push(start);
+                    new_loop.reverse();
+                    best_loop = new_loop;
+                    break 'search;
+                }
+
+                if is_in_component(neighbor) && !parents.contains_key(&neighbor) {
+                    parents.insert(neighbor, node_idx);
+                    queue.push_back((neighbor, depth + 1));
+                }
+            }
+        }
+
+        if best_loop.len() == 2 {
+            // a module importing itself can't be beaten
+            break;
+        }
+    }
+
+    return best_loop;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestProject;
+
+    #[test]
+    fn cycles_are_listed_largest_first_with_their_shortest_loop() {
+        let project = TestProject::new(&[
+            ("a.ts", "import './b';"),
+            ("b.ts", "import './c';\nimport './a';"),
+            ("c.ts", "import './a';"),
+            ("d.ts", "import type { E } from './e';\nexport type D = 1;"),
+            ("e.ts", "import type { D } from './d';\nexport type E = 1;"),
+            ("f.ts", "import './a';"),
+        ]);
+        let graph = project.build_graph();
+
+        let cycles = get_cycles(&graph, false).unwrap();
+        assert_eq!(cycles.len(), 2);
+        assert_eq!(
+            cycles[0].modules,
+            vec![
+                project.path("a.ts"),
+                project.path("b.ts"),
+                project.path("c.ts")
+            ]
+        );
+        // a -> b -> a is shorter than the loop through c
+        let shortest_loop = &cycles[0].shortest_loop;
+        assert_eq!(shortest_loop.len(), 3);
+        assert_eq!(shortest_loop.first(), shortest_loop.last());
+        assert!(!shortest_loop.contains(&project.path("c.ts")));
+        assert_eq!(
+            cycles[1].modules,
+            vec![project.path("d.ts"), project.path("e.ts")]
+        );
+    }
+
+    #[test]
+    fn type_only_cycles_can_be_ignored() {
+        let project = TestProject::new(&[
+            ("a.ts", "import './b';"),
+            ("b.ts", "import type { A } from './a';"),
+        ]);
+        let graph = project.build_graph();
+
+        assert_eq!(get_cycles(&graph, false).unwrap().len(), 1);
+        assert!(get_cycles(&graph, true).unwrap().is_empty());
+    }
+}
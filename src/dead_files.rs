@@ -0,0 +1,85 @@
+use globset::GlobSet;
+use petgraph::Direction;
+use std::path::PathBuf;
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    file_system::is_declaration_file,
+    module::ModuleKind,
+    package_json::{resolve_entry_point, PackageJson},
+};
+
+pub struct DeadFiles {
+    pub entry_point_count: usize,
+    /// package.json entry points which could not be matched to a file in the search paths
+    pub unresolved_entry_points: Vec<PathBuf>,
+    pub dead_files: Vec<PathBuf>,
+}
+
+/// Finds every file which cannot be reached from any of the entry points
+pub fn get_dead_files<'a>(
+    graph: &'a DependencyGraph,
+    entry_points: &GlobSet,
+    package_jsons: &[PackageJson],
+) -> Result<DeadFiles, &'a str> {
+    let mut unresolved_entry_points = vec![];
+    let mut roots = graph
+        .get_module_ids()
+        .filter(|module_id| graph.get_module_kind(*module_id) == ModuleKind::File)
+        .filter(|module_id| entry_points.is_match(graph.get_path_for_module_id(*module_id)))
+        .collect::<Vec<_>>();
+
+    for package_json in package_jsons {
+        for entry_point in package_json.entry_points.iter() {
+            let modules = resolve_entry_point(graph, entry_point);
+            if modules.is_empty() {
+                unresolved_entry_points.push(entry_point.clone());
+            }
+            roots.extend(modules);
+        }
+    }
+    roots.sort_by_key(|module_id| usize::from(*module_id));
+    roots.dedup();
+
+    let reachable = graph.get_reachable_module_ids(&roots, Direction::Outgoing)?;
+
+    let mut dead_files = graph
+        .get_module_ids()
+        .filter(|module_id| graph.get_module_kind(*module_id) == ModuleKind::File)
+        .filter(|module_id| !reachable.contains(module_id))
+        .map(|module_id| graph.get_path_for_module_id(module_id))
+        // declaration files are often included ambiently via the tsconfig rather than being imported
+        .filter(|path| !is_declaration_file(path))
+        .collect::<Vec<_>>();
+    dead_files.sort();
+
+    return Ok(DeadFiles {
+        entry_point_count: roots.len(),
+        unresolved_entry_points,
+        dead_files,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{file_system::build_glob_set_relative_to, test_utils::TestProject};
+
+    #[test]
+    fn files_unreachable_from_the_entry_points_are_dead() {
+        let project = TestProject::new(&[
+            ("index.ts", "import { a } from './a';"),
+            ("a.ts", "export const a = 1;"),
+            ("b.ts", "import { a } from './a';\nexport const b = a;"),
+            ("globals.d.ts", "declare const VERSION: string;"),
+            ("types/env.d.mts", "export {};"),
+        ]);
+        let graph = project.build_graph();
+        let entry_points =
+            build_glob_set_relative_to(&["index.ts".to_string()], &project.root).unwrap();
+
+        let dead_files = get_dead_files(&graph, &entry_points, &[]).unwrap();
+        assert_eq!(dead_files.entry_point_count, 1);
+        assert_eq!(dead_files.dead_files, vec![project.path("b.ts")]);
+    }
+}
@@ -1,52 +1,141 @@
 use clean_path::Clean;
+use globset::{Glob, GlobBuilder, GlobMatcher};
 use petgraph::{
     graph::{DiGraph, NodeIndex},
+    visit::EdgeRef,
     Direction,
 };
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use spliter::ParallelSpliterator;
 use std::{
     collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
 };
 
 use crate::{
-    dependency_graph_store::DependencyGraphStore,
+    breadth_first_expansion::BreadthFirstExpansion,
+    dependency_graph_store::{get_path_without_extension, DependencyGraphStore},
     depth_first_expansion::DepthFirstExpansion,
+    diagnostic::{Diagnostic, DiagnosticRule},
     file_system::extensions,
-    module::{EdgeWeight, Module, ModuleGraph, ModuleId},
+    import_visitor::{
+        escape_glob, Dependency, ExportBinding, ImportBinding, ParsedModule, SourceLocation,
+        DEFAULT_EXPORT_NAME,
+    },
+    module::{DependencyKind, EdgeWeight, Module, ModuleGraph, ModuleId, ModuleKind},
+    package_json::PackageJson,
     tsconfig::TSConfig,
 };
 
-type ImportResolutionErrors = HashMap<PathBuf, Vec<String>>;
+type ImportResolutionErrors = HashMap<PathBuf, Vec<Diagnostic>>;
 
-// these two pieces of data are intrinsically linked and will either both exist or not exist
+// these pieces of data are intrinsically linked and will either all exist or not exist
 // hence they sit on a separate struct, rather than directly on DependencyGraph
 struct GraphData {
     graph: ModuleGraph,
     module_id_to_node_idx: Vec<NodeIndex>,
+    // the imports and exports for each file module - node modules have no parsed data
+    module_id_to_parsed_module: Vec<Option<ParsedModule>>,
+    // the absolute paths that relative imports failed to resolve to, and the modules which import them
+    unresolved_imports: HashMap<PathBuf, Vec<ModuleId>>,
+    // the resolved glob for every wildcard import, and the module which declared it
+    wildcard_imports: Vec<(GlobMatcher, ModuleId)>,
+}
+
+/// A module found by a traversal
+#[derive(PartialEq)]
+pub struct DiscoveredModule {
+    pub path: PathBuf,
+    /// The minimum number of imports between any of the starting modules and this module
+    pub depth: u32,
+    /// The module this module was first reached from - `None` for the starting modules
+    pub parent: Option<PathBuf>,
 }
 
 pub struct DependencyGraph {
     dependency_graph_store: DependencyGraphStore,
     graph_data: Option<GraphData>,
+    package_jsons: Vec<PackageJson>,
+    // only packages with a name can be imported by other packages
+    package_name_to_index: HashMap<String, usize>,
+    package_directory_to_index: HashMap<PathBuf, usize>,
+    // for a collapsed graph, the group each of the original files was collapsed into
+    group_for_file: HashMap<PathBuf, PathBuf>,
 }
 impl DependencyGraph {
-    pub fn new(paths: &Vec<PathBuf>, tsconfig: &TSConfig) -> Self {
+    pub fn new(paths: &Vec<PathBuf>, tsconfig: &TSConfig, package_jsons: Vec<PackageJson>) -> Self {
         let dependency_graph_store = DependencyGraphStore::new(&paths, &tsconfig);
+        return DependencyGraph::from_store(dependency_graph_store, package_jsons);
+    }
+
+    fn from_store(
+        dependency_graph_store: DependencyGraphStore,
+        package_jsons: Vec<PackageJson>,
+    ) -> Self {
+        let package_name_to_index = package_jsons
+            .iter()
+            .enumerate()
+            .filter_map(|(index, package_json)| Some((package_json.name.clone()?, index)))
+            .collect();
+        let package_directory_to_index = package_jsons
+            .iter()
+            .enumerate()
+            .map(|(index, package_json)| (package_json.directory().to_path_buf(), index))
+            .collect();
 
         return DependencyGraph {
             graph_data: None,
             dependency_graph_store,
+            package_jsons,
+            package_name_to_index,
+            package_directory_to_index,
+            group_for_file: HashMap::new(),
+        };
+    }
+
+    /// Resolves a bare import of a package within the search paths to a file.
+    /// The package's public subpaths are tried first, followed by the file at the subpath within the package so that
+    /// deep imports into a package's internals resolve too.
+    fn resolve_workspace_import(&self, specifier: &Path) -> Option<Module> {
+        let specifier = specifier.to_string_lossy();
+        let package_name_component_count = if specifier.starts_with('@') { 2 } else { 1 };
+        let mut components = specifier.splitn(package_name_component_count + 1, '/');
+        let package_name = components
+            .by_ref()
+            .take(package_name_component_count)
+            .collect::<Vec<_>>()
+            .join("/");
+        let subpath = match components.next() {
+            Some(subpath) => format!("./{}", subpath),
+            None => String::from("."),
         };
+
+        let package_json = &self.package_jsons[*self.package_name_to_index.get(&package_name)?];
+        let mut candidates = package_json.resolve_public_subpath(&subpath);
+        candidates.push(package_json.directory().join(&subpath).clean());
+
+        return candidates.iter().find_map(|candidate| {
+            // entry points commonly refer to built files (eg `index.js`) so we also try the extension-less path to
+            // find the source file (eg `index.ts`)
+            return [candidate.to_path_buf(), candidate.with_extension("")]
+                .iter()
+                .filter_map(|path| self.dependency_graph_store.try_get_module_for_path(path))
+                .find(|module| {
+                    self.dependency_graph_store.get_module_kind(module) == ModuleKind::File
+                });
+        });
     }
 
     fn resolve_dependencies_for_module(
         &mut self,
         resolution_errors: &mut Vec<ResolutionError>,
+        wildcard_imports: &mut Vec<(GlobMatcher, ModuleId)>,
         owner_path: &PathBuf,
-        dependencies: &Vec<PathBuf>,
-    ) -> Vec<(ModuleId, ModuleId)> {
+        dependencies: &Vec<Dependency>,
+    ) -> Vec<(ModuleId, ModuleId, EdgeWeight)> {
         let owner = self
             .dependency_graph_store
             .try_get_module_for_path(&owner_path)
@@ -54,8 +143,15 @@ impl DependencyGraph {
         let parent = owner_path.parent().expect("Path should not be the root");
 
         let resolved_dependencies_for_module = dependencies.iter()
-            .filter_map(|dependency| {
-                if let Some(extension) = dependency.extension() {
+            .enumerate()
+            .flat_map(|(dependency_index, dependency)| {
+                let weight = EdgeWeight {
+                    kind: dependency.kind,
+                    dependency_index: dependency_index as u32,
+                    is_type_only: dependency.is_type_only,
+                };
+                let dependency_path = &dependency.specifier;
+                if let Some(extension) = dependency_path.extension() {
                     // TODO(bradzacher) - we will want to track these eventually so we can understand that
                     //                    changes to these file types will cause changes to the importing JS
                     match extension.to_str().unwrap() {
@@ -83,33 +179,61 @@ impl DependencyGraph {
                         extensions::WEBM |
                         extensions::WOFF |
                         extensions::WOFF2 => {
-                            return None;
+                            if dependency.kind == DependencyKind::WildcardDynamic {
+                                // a static import of one of these files is fine to skip, but a pattern silently
+                                // resolving to nothing would hide every file it was meant to depend on
+                                resolution_errors.push(ResolutionError {
+                                    rule: DiagnosticRule::UnresolvedWildcardImport,
+                                    location: dependency.location,
+                                    message: format!(
+                                        "Unable to resolve wildcard import \"{}\" as only source files are tracked",
+                                        dependency_path.display(),
+                                    ),
+                                    unresolved_path: None,
+                                });
+                            }
+                            return vec![];
                         },
                         _ => {}
                     }
                 }
 
-                if dependency.starts_with("../") || dependency.starts_with("./") {
+                if dependency.kind == DependencyKind::WildcardDynamic {
+                    return self
+                        .resolve_wildcard_dependency(resolution_errors, wildcard_imports, owner, parent, dependency_path, dependency.location)
+                        .into_iter()
+                        .map(|module| (owner.module_id, module.module_id, weight))
+                        .collect();
+                }
+
+                if dependency_path.starts_with("../") || dependency_path.starts_with("./") {
                     // dependency is a relative reference which we must resolve relative to the owner file
-                    let resolved_dependency_path = parent.join(dependency).clean();
+                    let resolved_dependency_path = parent.join(dependency_path).clean();
                     if let Some(resolved_dependency) = self.dependency_graph_store.try_get_module_for_path(&resolved_dependency_path) {
-                        return Some((owner.module_id.to_owned(), resolved_dependency.module_id.to_owned()));
+                        return vec![(owner.module_id.to_owned(), resolved_dependency.module_id.to_owned(), weight)];
                     }
 
                     resolution_errors.push(ResolutionError {
-                        module: owner,
+                        rule: DiagnosticRule::UnresolvedImport,
+                        location: dependency.location,
                         message: format!(
                             "Unable to resolve relative import \"{}\" to an existing module, tried \"{}\"",
-                            dependency.display(),
+                            dependency_path.display(),
                             resolved_dependency_path.display(),
-                        )
+                        ),
+                        unresolved_path: Some(resolved_dependency_path),
                     });
-                    return None;
+                    return vec![];
                 }
 
                 // check if it exists as-is in the module map
-                if let Some(existing_dep) = self.dependency_graph_store.try_get_module_for_path(dependency) {
-                    return Some((owner.module_id.to_owned(), existing_dep.module_id.to_owned()));
+                if let Some(existing_dep) = self.dependency_graph_store.try_get_module_for_path(dependency_path) {
+                    return vec![(owner.module_id.to_owned(), existing_dep.module_id.to_owned(), weight)];
+                }
+
+                // check if it's a package within the search paths - eg a monorepo workspace package
+                if let Some(workspace_dep) = self.resolve_workspace_import(dependency_path) {
+                    return vec![(owner.module_id.to_owned(), workspace_dep.module_id.to_owned(), weight)];
                 }
 
                 // assume it's a new, never before seen node_module and assign a new ModuleID for it
@@ -117,116 +241,1254 @@ impl DependencyGraph {
                 // note that we don't care about deep imports and just want the top-level node module name
                 // eg we don't care that `A -> mod/foo` and `B -> mod/bar`, we just care that `(A, B) -> mod`
 
-                let new_node_module = self.dependency_graph_store.add_node_module(dependency);
-                return Some((owner.module_id.to_owned(), new_node_module.module_id.to_owned()));
+                let new_node_module = self.dependency_graph_store.add_node_module(dependency_path);
+                return vec![(owner.module_id.to_owned(), new_node_module.module_id.to_owned(), weight)];
             }).collect::<Vec<_>>();
 
         return resolved_dependencies_for_module;
     }
 
-    pub fn resolve_imports(
+    /// Resolves a glob import pattern (eg `./locales/*`) against the discovered files
+    fn resolve_wildcard_dependency(
+        &mut self,
+        resolution_errors: &mut Vec<ResolutionError>,
+        wildcard_imports: &mut Vec<(GlobMatcher, ModuleId)>,
+        owner: Module,
+        parent: &Path,
+        pattern: &Path,
+        location: SourceLocation,
+    ) -> Vec<Module> {
+        if !pattern.starts_with("../") && !pattern.starts_with("./") {
+            // a node module pattern like `lodash/${name}` can still be resolved so long as the package name is static
+            let package_name_component_count = if pattern.to_string_lossy().starts_with('@') {
+                2
+            } else {
+                1
+            };
+            let is_package_name_static = pattern
+                .components()
+                .take(package_name_component_count)
+                .all(|component| !component.as_os_str().to_string_lossy().contains(['*', '[']));
+            if is_package_name_static && pattern.components().count() > package_name_component_count
+            {
+                return vec![self.dependency_graph_store.add_node_module(pattern)];
+            }
+
+            resolution_errors.push(ResolutionError {
+                rule: DiagnosticRule::UnresolvedWildcardImport,
+                location,
+                message: format!(
+                    "Unable to resolve wildcard import \"{}\" as it does not have a static relative path or package name",
+                    pattern.display(),
+                ),
+                unresolved_path: None,
+            });
+            return vec![];
+        }
+
+        let resolved_pattern = parent.join(pattern).clean();
+        // only the pattern is a glob - the directory of the importing file could contain meta characters too, eg
+        // `pages/[id]`, so it has to be matched literally
+        let escaped_parent = PathBuf::from(escape_glob(&parent.to_string_lossy()));
+        let glob_pattern = escaped_parent.join(pattern).clean();
+        let glob = match build_wildcard_glob(&glob_pattern.to_string_lossy()) {
+            Ok(glob) => glob.compile_matcher(),
+            Err(e) => {
+                resolution_errors.push(ResolutionError {
+                    rule: DiagnosticRule::UnresolvedWildcardImport,
+                    location,
+                    message: format!(
+                        "Unable to parse wildcard import \"{}\" - {}",
+                        pattern.display(),
+                        e,
+                    ),
+                    unresolved_path: None,
+                });
+                return vec![];
+            }
+        };
+
+        let modules = self
+            .dependency_graph_store
+            .get_file_modules_matching_glob(&glob)
+            .into_iter()
+            // a pattern can easily match the file that declared it, but a module never depends on itself
+            .filter(|module| *module != owner)
+            .collect::<Vec<_>>();
+        if modules.is_empty() {
+            resolution_errors.push(ResolutionError {
+                rule: DiagnosticRule::UnresolvedWildcardImport,
+                location,
+                message: format!(
+                    "Unable to resolve wildcard import \"{}\" to any existing modules, tried \"{}\"",
+                    pattern.display(),
+                    resolved_pattern.display(),
+                ),
+                unresolved_path: None,
+            });
+        }
+        wildcard_imports.push((glob, owner.module_id));
+
+        return modules;
+    }
+
+    /// Resolves the imports of a single file
+    fn resolve_module_imports(
         &mut self,
-        raw_dependencies: &[(&PathBuf, Vec<PathBuf>)],
-    ) -> Option<ImportResolutionErrors> {
-        // tracks the resolution errors we encounter
+        owner_path: &PathBuf,
+        dependencies: &Vec<Dependency>,
+    ) -> ResolvedImports {
         let mut resolution_errors: Vec<ResolutionError> = vec![];
+        let mut wildcard_imports: Vec<(GlobMatcher, ModuleId)> = vec![];
+        let resolved_dependencies = self.resolve_dependencies_for_module(
+            &mut resolution_errors,
+            &mut wildcard_imports,
+            owner_path,
+            dependencies,
+        );
 
-        let resolved_dependencies: Vec<(ModuleId, ModuleId)> = raw_dependencies
-            .iter()
-            .map(|(owner_path, dependencies)| {
-                return self.resolve_dependencies_for_module(
-                    &mut resolution_errors,
-                    owner_path,
-                    dependencies,
-                );
+        let imports = resolved_dependencies
+            .into_iter()
+            .map(|(_, to_module_id, weight)| {
+                let to_module = self.dependency_graph_store.get_module_for_id(to_module_id);
+                let to_kind = self.dependency_graph_store.get_module_kind(&to_module);
+                let to_path = match to_kind {
+                    ModuleKind::File => self.dependency_graph_store.get_path_for_module(&to_module),
+                    // the deep import path is kept so that it is registered again when the import is reused
+                    ModuleKind::NodeModule => dependencies[weight.dependency_index as usize]
+                        .specifier
+                        .clone(),
+                };
+                return (to_path, to_kind, weight);
             })
-            .flatten()
             .collect();
 
-        // collect the errors
-        let resolution_errors = {
-            if resolution_errors.is_empty() {
-                None
-            } else {
-                let mut grouped: ImportResolutionErrors = HashMap::new();
-                for error in resolution_errors.iter() {
-                    let module_path = self
-                        .dependency_graph_store
-                        .get_path_for_module(&error.module);
-                    grouped
-                        .entry(module_path.clone())
-                        .or_insert(Vec::new())
-                        .push(error.message.clone());
-                }
-                Some(grouped)
+        return ResolvedImports {
+            imports,
+            unresolved_paths: resolution_errors
+                .iter()
+                .filter_map(|error| error.unresolved_path.clone())
+                .collect(),
+            wildcard_globs: wildcard_imports
+                .iter()
+                .map(|(glob, _)| glob.glob().glob().to_string())
+                .collect(),
+            errors: resolution_errors
+                .into_iter()
+                .map(|error| Diagnostic {
+                    rule: error.rule,
+                    message: error.message,
+                    location: error.location,
+                })
+                .collect(),
+        };
+    }
+
+    /// Resolves the imports of every file, reusing the given resolved imports for the files which have them.
+    /// The resolved imports of every file are returned so that they can be cached for the next run - they are only
+    /// valid for as long as the file, the tsconfig, the package.jsons and the set of files are unchanged.
+    pub fn resolve_imports(
+        &mut self,
+        parsed_modules: Vec<(&PathBuf, ParsedModule)>,
+        mut cached_resolved_imports: HashMap<PathBuf, ResolvedImports>,
+    ) -> (
+        Option<ImportResolutionErrors>,
+        Vec<(PathBuf, ResolvedImports)>,
+    ) {
+        let resolved_imports = parsed_modules
+            .iter()
+            .map(|(owner_path, parsed_module)| {
+                let resolved_imports = match cached_resolved_imports.remove(*owner_path) {
+                    Some(resolved_imports) => resolved_imports,
+                    None => self.resolve_module_imports(owner_path, &parsed_module.dependencies),
+                };
+                return ((*owner_path).clone(), resolved_imports);
+            })
+            .collect::<Vec<_>>();
+
+        let mut resolved_dependencies: Vec<(ModuleId, ModuleId, EdgeWeight)> = vec![];
+        // unresolved relative imports are kept so that we know who imported a file that no longer exists
+        let mut unresolved_imports: HashMap<PathBuf, Vec<ModuleId>> = HashMap::new();
+        let mut wildcard_imports: Vec<(GlobMatcher, ModuleId)> = vec![];
+        let mut resolution_errors: ImportResolutionErrors = HashMap::new();
+        for (owner_path, owner_resolved_imports) in resolved_imports.iter() {
+            let store = &mut self.dependency_graph_store;
+            let owner = store
+                .try_get_module_for_path(owner_path)
+                .expect("A module should have already been defined");
+            for (to_path, to_kind, weight) in owner_resolved_imports.imports.iter() {
+                let to = match to_kind {
+                    ModuleKind::File => store
+                        .try_get_module_for_path(to_path)
+                        .expect("A module should have already been defined"),
+                    ModuleKind::NodeModule => store.add_node_module(to_path),
+                };
+                resolved_dependencies.push((owner.module_id, to.module_id, *weight));
             }
+            for unresolved_path in owner_resolved_imports.unresolved_paths.iter() {
+                unresolved_imports
+                    .entry(unresolved_path.clone())
+                    .or_default()
+                    .push(owner.module_id);
+            }
+            for glob in owner_resolved_imports.wildcard_globs.iter() {
+                let glob =
+                    build_wildcard_glob(glob).expect("A cached wildcard glob should be valid");
+                wildcard_imports.push((glob.compile_matcher(), owner.module_id));
+            }
+            if !owner_resolved_imports.errors.is_empty() {
+                resolution_errors.insert(owner_path.clone(), owner_resolved_imports.errors.clone());
+            }
+        }
+
+        self.set_graph_data(
+            resolved_dependencies,
+            parsed_modules,
+            unresolved_imports,
+            wildcard_imports,
+        );
+
+        let resolution_errors = if resolution_errors.is_empty() {
+            None
+        } else {
+            Some(resolution_errors)
         };
+        return (resolution_errors, resolved_imports);
+    }
+
+    /// Creates a graph from modules whose imports have already been resolved - eg the groups of a collapsed graph.
+    /// Each import is `(importer path, imported path, imported module kind, weight)`, and every file must be one of
+    /// the given paths.
+    pub fn from_resolved_imports(
+        paths: &Vec<PathBuf>,
+        parsed_modules: Vec<(&PathBuf, ParsedModule)>,
+        imports: Vec<(PathBuf, PathBuf, ModuleKind, EdgeWeight)>,
+        package_jsons: Vec<PackageJson>,
+        group_for_file: HashMap<PathBuf, PathBuf>,
+    ) -> Self {
+        let mut dependency_graph = DependencyGraph::new(paths, &TSConfig::default(), package_jsons);
+        dependency_graph.group_for_file = group_for_file;
 
-        // add the resolved dependency graph to the backing graph
+        let resolved_dependencies = imports
+            .iter()
+            .map(|(from_path, to_path, to_kind, weight)| {
+                let store = &mut dependency_graph.dependency_graph_store;
+                let from = store
+                    .try_get_module_for_path(from_path)
+                    .expect("A module should have already been defined");
+                let to = match to_kind {
+                    ModuleKind::File => store
+                        .try_get_module_for_path(to_path)
+                        .expect("A module should have already been defined"),
+                    ModuleKind::NodeModule => store.add_node_module(to_path),
+                };
+                return (from.module_id, to.module_id, *weight);
+            })
+            .collect();
+
+        dependency_graph.set_graph_data(
+            resolved_dependencies,
+            parsed_modules,
+            HashMap::new(),
+            vec![],
+        );
+
+        return dependency_graph;
+    }
+
+    /// Writes the built graph in a compact binary format, so that it can be loaded without parsing or resolving any files.
+    /// Returns a description of the failure if it could not be written - eg because the directory doesn't exist
+    pub fn write_binary(&self, path: &Path) -> Result<(), String> {
+        let graph_data = self
+            .graph_data
+            .as_ref()
+            .ok_or("Cannot call write_binary before resolve_imports")?;
+
+        let binary_graph = BinaryGraphRef {
+            store: &self.dependency_graph_store,
+            package_jsons: &self.package_jsons,
+            edges: graph_data
+                .graph
+                .edge_references()
+                .map(|edge| {
+                    return (
+                        graph_data.graph[edge.source()],
+                        graph_data.graph[edge.target()],
+                        *edge.weight(),
+                    );
+                })
+                .collect(),
+            parsed_modules: &graph_data.module_id_to_parsed_module,
+            unresolved_imports: &graph_data.unresolved_imports,
+            wildcard_imports: graph_data
+                .wildcard_imports
+                .iter()
+                .map(|(glob, module_id)| (glob.glob().glob(), *module_id))
+                .collect(),
+        };
+
+        let file = File::create(path)
+            .map_err(|e| format!("Unable to create graph {}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        writer
+            .write_all(BINARY_GRAPH_MAGIC)
+            .and_then(|_| writer.write_all(&BINARY_GRAPH_VERSION.to_le_bytes()))
+            .map_err(|e| format!("Unable to write graph {}: {}", path.display(), e))?;
+        rmp_serde::encode::write(&mut writer, &binary_graph)
+            .map_err(|e| format!("Unable to write graph {}: {}", path.display(), e))?;
+        // flushed explicitly as errors when the writer is dropped are ignored - eg a full disk
+        writer
+            .flush()
+            .map_err(|e| format!("Unable to write graph {}: {}", path.display(), e))?;
+
+        return Ok(());
+    }
+
+    /// Reads a graph written by `write_binary`
+    pub fn read_binary(path: &Path) -> Self {
+        let bytes =
+            fs::read(path).unwrap_or_else(|_| panic!("Unable to read graph {}", path.display()));
+        let header_length = BINARY_GRAPH_MAGIC.len() + 4;
+        if bytes.len() < header_length || !bytes.starts_with(BINARY_GRAPH_MAGIC) {
+            panic!("{} is not a graph written by save-graph", path.display());
+        }
+        let version = u32::from_le_bytes(
+            bytes[BINARY_GRAPH_MAGIC.len()..header_length]
+                .try_into()
+                .expect("The version should be 4 bytes"),
+        );
+        if version != BINARY_GRAPH_VERSION {
+            panic!(
+                "Graph {} has version {} but this version of athena reads version {}",
+                path.display(),
+                version,
+                BINARY_GRAPH_VERSION
+            );
+        }
+        let binary_graph: BinaryGraph = rmp_serde::from_slice(&bytes[header_length..])
+            .unwrap_or_else(|e| panic!("Unable to parse graph {}: {}", path.display(), e));
+
+        let mut dependency_graph_store = binary_graph.store;
+        dependency_graph_store.rebuild_path_lookup();
+        let mut dependency_graph =
+            DependencyGraph::from_store(dependency_graph_store, binary_graph.package_jsons);
+        let (graph, module_id_to_node_idx) =
+            dependency_graph.build_module_graph(binary_graph.edges);
+        let wildcard_imports = binary_graph
+            .wildcard_imports
+            .iter()
+            .map(|(glob, module_id)| {
+                let glob =
+                    build_wildcard_glob(glob).expect("A saved wildcard glob should be valid");
+                return (glob.compile_matcher(), *module_id);
+            })
+            .collect();
+        dependency_graph.graph_data = Some(GraphData {
+            graph,
+            module_id_to_node_idx,
+            module_id_to_parsed_module: binary_graph.parsed_modules,
+            unresolved_imports: binary_graph.unresolved_imports,
+            wildcard_imports,
+        });
+
+        return dependency_graph;
+    }
+
+    /// Creates the backing graph, with a node for every module in module ID order
+    fn build_module_graph(
+        &self,
+        resolved_dependencies: Vec<(ModuleId, ModuleId, EdgeWeight)>,
+    ) -> (ModuleGraph, Vec<NodeIndex>) {
         let modules = self.dependency_graph_store.modules();
-        let module_count = modules.len();
         let mut graph: ModuleGraph =
-            DiGraph::with_capacity(module_count, resolved_dependencies.len());
+            DiGraph::with_capacity(modules.len(), resolved_dependencies.len());
         let mut module_id_to_node_idx = Vec::with_capacity(modules.len());
         for module in modules.iter() {
             module_id_to_node_idx.insert(module.module_id.into(), graph.add_node(module.module_id));
         }
-        for (from_id, to_id) in resolved_dependencies {
+        for (from_id, to_id, weight) in resolved_dependencies {
             graph.add_edge(
                 module_id_to_node_idx[from_id],
                 module_id_to_node_idx[to_id],
-                EdgeWeight,
+                weight,
             );
         }
+        return (graph, module_id_to_node_idx);
+    }
+
+    fn set_graph_data(
+        &mut self,
+        resolved_dependencies: Vec<(ModuleId, ModuleId, EdgeWeight)>,
+        parsed_modules: Vec<(&PathBuf, ParsedModule)>,
+        unresolved_imports: HashMap<PathBuf, Vec<ModuleId>>,
+        wildcard_imports: Vec<(GlobMatcher, ModuleId)>,
+    ) {
+        let module_count = self.dependency_graph_store.modules().len();
+        let (graph, module_id_to_node_idx) = self.build_module_graph(resolved_dependencies);
+        let mut module_id_to_parsed_module = vec![None; module_count];
+        for (path, parsed_module) in parsed_modules {
+            let module = self
+                .dependency_graph_store
+                .try_get_module_for_path(path)
+                .expect("A module should have already been defined");
+            module_id_to_parsed_module[usize::from(module.module_id)] = Some(parsed_module);
+        }
+
         self.graph_data = Some(GraphData {
             graph,
             module_id_to_node_idx,
+            module_id_to_parsed_module,
+            unresolved_imports,
+            wildcard_imports,
         });
+    }
 
-        return resolution_errors;
+    /// Finds the modules which directly import or re-export the given export name from a file.
+    /// Namespace and call-like imports (eg `import()`) are included as they could reference any export.
+    pub fn get_importers_of_export(
+        &self,
+        path: &Path,
+        export_name: &str,
+    ) -> Result<Vec<(PathBuf, SourceLocation)>, &str> {
+        let graph_data = self
+            .graph_data
+            .as_ref()
+            .ok_or("Cannot call get_importers_of_export before resolve_imports")?;
+
+        let module_id = self
+            .dependency_graph_store
+            .try_get_module_for_path(path)
+            .ok_or("Unable to get module for path")?
+            .module_id;
+
+        let mut importers = vec![];
+        for edge in graph_data.graph.edges_directed(
+            graph_data.module_id_to_node_idx[module_id],
+            Direction::Incoming,
+        ) {
+            let importer_id = graph_data.graph[edge.source()];
+            let importer = self.dependency_graph_store.get_module_for_id(importer_id);
+            let parsed_module = match &graph_data.module_id_to_parsed_module[importer_id] {
+                Some(parsed_module) => parsed_module,
+                None => continue,
+            };
+            let dependency = &parsed_module.dependencies[edge.weight().dependency_index as usize];
+
+            let is_imported = dependency.imports.iter().any(|binding| match binding {
+                ImportBinding::Default { .. } => export_name == DEFAULT_EXPORT_NAME,
+                ImportBinding::Named { imported, .. } => imported == export_name,
+                ImportBinding::Namespace { .. } => true,
+            });
+            let is_re_exported = parsed_module.exports.iter().any(|export| match export {
+                ExportBinding::ReExport {
+                    imported,
+                    dependency_index,
+                    ..
+                } => {
+                    *dependency_index == edge.weight().dependency_index as usize
+                        && imported
+                            .as_ref()
+                            .is_none_or(|imported| imported == export_name)
+                }
+                ExportBinding::ReExportAll {
+                    dependency_index, ..
+                } => {
+                    *dependency_index == edge.weight().dependency_index as usize
+                        && export_name != DEFAULT_EXPORT_NAME
+                }
+                ExportBinding::Local { .. } => false,
+            });
+
+            if is_imported || is_re_exported {
+                importers.push((
+                    self.dependency_graph_store.get_path_for_module(&importer),
+                    dependency.location,
+                ));
+            }
+        }
+        importers.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.line.cmp(&b.1.line)));
+
+        return Ok(importers);
     }
 
+    /// Traverses from every given path at once - each module is reported once, with its minimum depth from any root
     pub fn get_all_dependencies(
         &self,
-        path: &Path,
+        paths: &[PathBuf],
         direction: Direction,
         max_depth: u32,
-    ) -> Result<HashSet<PathBuf>, &str> {
+    ) -> Result<Vec<DiscoveredModule>, &str> {
         let graph_data = self
             .graph_data
             .as_ref()
             .ok_or("Cannot call get_all_dependencies before resolve_imports")?;
 
-        let module_id = self
+        let bfe = BreadthFirstExpansion::new(
+            &graph_data.graph,
+            direction,
+            max_depth,
+            self.get_root_nodes(graph_data, paths)?,
+        );
+
+        let get_path = |node_idx: NodeIndex| {
+            let module_id = graph_data.graph.node_weight(node_idx).unwrap();
+            return self
+                .dependency_graph_store
+                .get_path_for_module(&self.dependency_graph_store.get_module_for_id(*module_id));
+        };
+
+        return Ok(bfe
+            .map(|visited_node| DiscoveredModule {
+                path: get_path(visited_node.node_idx),
+                depth: visited_node.depth,
+                parent: visited_node.parent.map(get_path),
+            })
+            .collect());
+    }
+
+    /// Attributes the result of a multi-root traversal back to the roots - ie for each module found, the sorted list
+    /// of roots which can reach it within `max_depth`.
+    /// This is a single breadth first pass which carries a set of roots per module rather than a traversal per root.
+    pub fn get_roots_reaching_dependencies(
+        &self,
+        paths: &[PathBuf],
+        direction: Direction,
+        max_depth: u32,
+    ) -> Result<HashMap<PathBuf, Vec<PathBuf>>, &str> {
+        let graph_data = self
+            .graph_data
+            .as_ref()
+            .ok_or("Cannot call get_roots_reaching_dependencies before resolve_imports")?;
+
+        let root_nodes = self.get_root_nodes(graph_data, paths)?;
+        let words = root_nodes.len().div_ceil(u64::BITS as usize);
+
+        // `frontier` holds the roots which first reached each module at the current depth - only those need to be
+        // passed on, so a root is propagated through a module once even when it is also reached from other roots
+        let mut frontier: HashMap<NodeIndex, Vec<u64>> = HashMap::new();
+        for (root_idx, root_node) in root_nodes.iter().enumerate() {
+            frontier.entry(*root_node).or_insert_with(|| vec![0; words])
+                [root_idx / u64::BITS as usize] |= 1 << (root_idx % u64::BITS as usize);
+        }
+        let mut reached = frontier.clone();
+
+        let mut depth = 0;
+        while !frontier.is_empty() && (max_depth == 0 || depth < max_depth) {
+            depth += 1;
+            let mut next_frontier: HashMap<NodeIndex, Vec<u64>> = HashMap::new();
+            for (node_idx, roots) in frontier.iter() {
+                for neighbor in graph_data.graph.neighbors_directed(*node_idx, direction) {
+                    let reached_roots = reached.entry(neighbor).or_insert_with(|| vec![0; words]);
+                    for (word_idx, word) in roots.iter().enumerate() {
+                        let new_roots = word & !reached_roots[word_idx];
+                        if new_roots != 0 {
+                            reached_roots[word_idx] |= new_roots;
+                            next_frontier
+                                .entry(neighbor)
+                                .or_insert_with(|| vec![0; words])[word_idx] |= new_roots;
+                        }
+                    }
+                }
+            }
+            frontier = next_frontier;
+        }
+
+        return Ok(reached
+            .into_iter()
+            .map(|(node_idx, root_words)| {
+                let mut roots = (0..root_nodes.len())
+                    .filter(|root_idx| {
+                        root_words[root_idx / u64::BITS as usize]
+                            & (1 << (root_idx % u64::BITS as usize))
+                            != 0
+                    })
+                    .map(|root_idx| paths[root_idx].clone())
+                    .collect::<Vec<_>>();
+                // the same file can be given more than once
+                roots.sort();
+                roots.dedup();
+                return (
+                    self.get_path_for_module_id(graph_data.graph[node_idx]),
+                    roots,
+                );
+            })
+            .collect());
+    }
+
+    fn get_root_nodes(
+        &self,
+        graph_data: &GraphData,
+        paths: &[PathBuf],
+    ) -> Result<Vec<NodeIndex>, &str> {
+        return paths
+            .iter()
+            .map(|path| {
+                let module_id = self
+                    .dependency_graph_store
+                    .try_get_module_for_path(path)
+                    .ok_or("Unable to get module for path")?
+                    .module_id;
+                return Ok(graph_data.module_id_to_node_idx[module_id]);
+            })
+            .collect();
+    }
+}
+
+// Incremental updates - these keep a built graph in sync with changes to the files in the search paths.
+// Nodes are kept in module ID order, so removing a module's node moves the last node into its index in the same way
+// that the store moves the last module into its ID.
+impl DependencyGraph {
+    /// Updates the graph after a file has been created or changed.
+    /// The file's edges are only replaced if its dependency list has changed, and any files whose imports could now
+    /// resolve differently (eg an unresolved import of a new file) are resolved again - the errors from resolving each
+    /// of these files are returned.
+    pub fn update_file(
+        &mut self,
+        path: &Path,
+        parsed_module: ParsedModule,
+        tsconfig: &TSConfig,
+    ) -> Result<ImportResolutionErrors, &str> {
+        let graph_data = self
+            .graph_data
+            .as_mut()
+            .ok_or("Cannot call update_file before resolve_imports")?;
+
+        let existing_module = self
             .dependency_graph_store
-            .try_get_module_for_path(&path)
-            .ok_or("Unable to get module for path")?
-            .module_id;
+            .try_get_module_for_path(path)
+            .filter(|module| self.dependency_graph_store.get_path_for_module(module) == path);
+        if let Some(module) = existing_module {
+            let is_unchanged = graph_data.module_id_to_parsed_module[usize::from(module.module_id)]
+                .replace(parsed_module.clone())
+                .is_some_and(|previous| {
+                    is_same_dependency_list(&previous.dependencies, &parsed_module.dependencies)
+                });
+            if is_unchanged {
+                return Ok(HashMap::new());
+            }
+            return Ok(self.resolve_files_again(vec![path.to_path_buf()]));
+        }
+
+        let importers_of_path = self
+            .get_importers_of_missing_path(path)
+            .expect("The graph should be built");
+        let (module, displaced_modules) = self.dependency_graph_store.add_file(path, tsconfig);
+        let graph_data = self.graph_data.as_mut().expect("The graph should be built");
+        let node_idx = graph_data.graph.add_node(module.module_id);
+        graph_data.module_id_to_node_idx.push(node_idx);
+        graph_data
+            .module_id_to_parsed_module
+            .push(Some(parsed_module));
+
+        // the new file can be imported by paths which previously failed to resolve or resolved to another module
+        let mut files_to_resolve = vec![path.to_path_buf()];
+        files_to_resolve.extend(
+            importers_of_path
+                .into_iter()
+                .map(|module_id| self.get_path_for_module_id(module_id)),
+        );
+        for displaced_module in displaced_modules {
+            files_to_resolve.extend(self.get_importer_paths(displaced_module.module_id));
+        }
+
+        return Ok(self.resolve_files_again(files_to_resolve));
+    }
+
+    /// Updates the graph after a file has been deleted, resolving the files which imported it again.
+    /// The errors from resolving each of these files are returned.
+    pub fn remove_file(
+        &mut self,
+        path: &Path,
+        tsconfig: &TSConfig,
+    ) -> Result<ImportResolutionErrors, &str> {
+        if self.graph_data.is_none() {
+            return Err("Cannot call remove_file before resolve_imports");
+        }
+
+        let module = match self.dependency_graph_store.try_get_module_for_path(path) {
+            Some(module) if self.dependency_graph_store.get_path_for_module(&module) == path => {
+                module
+            }
+            _ => return Ok(HashMap::new()),
+        };
+        let importers = self.get_importer_paths(module.module_id);
+        let node_modules = self.get_imported_node_module_paths(module.module_id);
+
+        let removed_paths = self.remove_module(module);
+        self.dependency_graph_store
+            .reassign_paths(&removed_paths, tsconfig);
+
+        let resolution_errors = self.resolve_files_again(importers);
+        self.remove_unused_node_modules(node_modules);
+
+        return Ok(resolution_errors);
+    }
+
+    /// Resolves the imports of the given files again and replaces their outgoing edges.
+    /// Every file which was resolved is in the returned errors, with an empty list if all of its imports resolved.
+    fn resolve_files_again(&mut self, mut paths: Vec<PathBuf>) -> ImportResolutionErrors {
+        paths.sort();
+        paths.dedup();
+
+        let mut resolution_errors: ImportResolutionErrors = HashMap::new();
+        let mut previous_node_modules = vec![];
+        for path in paths {
+            let module = match self.dependency_graph_store.try_get_module_for_path(&path) {
+                Some(module) => module,
+                // the file was removed earlier in the same batch of changes
+                None => continue,
+            };
+            let dependencies = self
+                .get_parsed_module(module.module_id)
+                .expect("A file module should have been parsed")
+                .dependencies
+                .clone();
+            previous_node_modules.extend(self.get_imported_node_module_paths(module.module_id));
+
+            let resolved_imports = self.resolve_module_imports(&path, &dependencies);
+            self.set_module_imports(module, &resolved_imports);
+            // files which now resolve cleanly are included too, so that a later resolution of a file in the same batch
+            // replaces the errors from an earlier one
+            resolution_errors.insert(path, resolved_imports.errors);
+        }
+        self.remove_unused_node_modules(previous_node_modules);
+
+        return resolution_errors;
+    }
+
+    /// Replaces the outgoing edges of a module with the given imports
+    fn set_module_imports(&mut self, owner: Module, resolved_imports: &ResolvedImports) {
+        let store = &mut self.dependency_graph_store;
+        let graph_data = self.graph_data.as_mut().expect("The graph should be built");
+        let owner_node_idx = graph_data.module_id_to_node_idx[owner.module_id];
+
+        while let Some(edge) = graph_data
+            .graph
+            .first_edge(owner_node_idx, Direction::Outgoing)
+        {
+            graph_data.graph.remove_edge(edge);
+        }
+        graph_data.unresolved_imports.retain(|_, importers| {
+            importers.retain(|module_id| *module_id != owner.module_id);
+            return !importers.is_empty();
+        });
+        graph_data
+            .wildcard_imports
+            .retain(|(_, module_id)| *module_id != owner.module_id);
+
+        for (to_path, to_kind, weight) in resolved_imports.imports.iter() {
+            let to = match to_kind {
+                ModuleKind::File => store
+                    .try_get_module_for_path(to_path)
+                    .expect("A module should have already been defined"),
+                ModuleKind::NodeModule => store.add_node_module(to_path),
+            };
+            // node modules which weren't imported before are new modules, so they need a node
+            while graph_data.module_id_to_node_idx.len() < store.modules().len() {
+                let module_id = ModuleId::from(graph_data.module_id_to_node_idx.len());
+                graph_data
+                    .module_id_to_node_idx
+                    .push(graph_data.graph.add_node(module_id));
+                graph_data.module_id_to_parsed_module.push(None);
+            }
+            graph_data.graph.add_edge(
+                owner_node_idx,
+                graph_data.module_id_to_node_idx[to.module_id],
+                *weight,
+            );
+        }
+        for unresolved_path in resolved_imports.unresolved_paths.iter() {
+            graph_data
+                .unresolved_imports
+                .entry(unresolved_path.clone())
+                .or_default()
+                .push(owner.module_id);
+        }
+        for glob in resolved_imports.wildcard_globs.iter() {
+            let glob = build_wildcard_glob(glob).expect("A resolved wildcard glob should be valid");
+            graph_data
+                .wildcard_imports
+                .push((glob.compile_matcher(), owner.module_id));
+        }
+    }
+
+    /// Removes a module and its edges, and returns the paths which resolved to it
+    fn remove_module(&mut self, module: Module) -> Vec<PathBuf> {
+        let graph_data = self.graph_data.as_mut().expect("The graph should be built");
+        let removed_id = usize::from(module.module_id);
+        let last_module_id = ModuleId::from(graph_data.module_id_to_node_idx.len() - 1);
+
+        let removed_node_idx = graph_data.module_id_to_node_idx[module.module_id];
+        graph_data.graph.remove_node(removed_node_idx);
+        graph_data.module_id_to_node_idx.pop();
+        graph_data
+            .module_id_to_parsed_module
+            .swap_remove(removed_id);
+        if module.module_id != last_module_id {
+            graph_data.graph[removed_node_idx] = module.module_id;
+        }
+
+        let move_module_id = |module_id: &mut ModuleId| {
+            if *module_id == last_module_id {
+                *module_id = module.module_id;
+            }
+        };
+        graph_data.unresolved_imports.retain(|_, importers| {
+            importers.retain(|module_id| *module_id != module.module_id);
+            importers.iter_mut().for_each(move_module_id);
+            return !importers.is_empty();
+        });
+        graph_data
+            .wildcard_imports
+            .retain(|(_, module_id)| *module_id != module.module_id);
+        graph_data
+            .wildcard_imports
+            .iter_mut()
+            .for_each(|(_, module_id)| move_module_id(module_id));
+
+        return self.dependency_graph_store.remove_module(&module);
+    }
+
+    /// Removes the given node modules if they are no longer imported by any file
+    fn remove_unused_node_modules(&mut self, paths: Vec<PathBuf>) {
+        for path in paths {
+            let module = match self.dependency_graph_store.try_get_module_for_path(&path) {
+                Some(module)
+                    if self.dependency_graph_store.get_module_kind(&module)
+                        == ModuleKind::NodeModule =>
+                {
+                    module
+                }
+                // already removed, as the paths can contain the same node module more than once
+                _ => continue,
+            };
+            if self.get_importer_paths(module.module_id).is_empty() {
+                self.remove_module(module);
+            }
+        }
+    }
+
+    /// The files which import a module
+    fn get_importer_paths(&self, module_id: ModuleId) -> Vec<PathBuf> {
+        let graph_data = self.graph_data.as_ref().expect("The graph should be built");
+        return graph_data
+            .graph
+            .neighbors_directed(
+                graph_data.module_id_to_node_idx[module_id],
+                Direction::Incoming,
+            )
+            .filter(|node_idx| graph_data.graph[*node_idx] != module_id)
+            .map(|node_idx| self.get_path_for_module_id(graph_data.graph[node_idx]))
+            .collect();
+    }
+
+    /// The node modules imported by a module
+    fn get_imported_node_module_paths(&self, module_id: ModuleId) -> Vec<PathBuf> {
+        let graph_data = self.graph_data.as_ref().expect("The graph should be built");
+        return graph_data
+            .graph
+            .neighbors_directed(
+                graph_data.module_id_to_node_idx[module_id],
+                Direction::Outgoing,
+            )
+            .map(|node_idx| graph_data.graph[node_idx])
+            .filter(|module_id| self.get_module_kind(*module_id) == ModuleKind::NodeModule)
+            .map(|module_id| self.get_path_for_module_id(module_id))
+            .collect();
+    }
+}
+
+// Graph accessors - these allow analyses to walk the raw graph
+impl DependencyGraph {
+    pub fn get_graph(&self) -> Result<&ModuleGraph, &str> {
+        return self
+            .graph_data
+            .as_ref()
+            .map(|graph_data| &graph_data.graph)
+            .ok_or("Cannot call get_graph before resolve_imports");
+    }
+
+    pub fn get_node_for_module_id(&self, module_id: ModuleId) -> NodeIndex {
+        let graph_data = self
+            .graph_data
+            .as_ref()
+            .expect("Cannot call get_node_for_module_id before resolve_imports");
+        return graph_data.module_id_to_node_idx[module_id];
+    }
+
+    /// Gets the imports and exports extracted from a file - node modules have no parsed data
+    pub fn get_parsed_module(&self, module_id: ModuleId) -> Option<&ParsedModule> {
+        return self.graph_data.as_ref()?.module_id_to_parsed_module[module_id].as_ref();
+    }
+
+    /// Gets the dependency which created the given edge
+    pub fn get_dependency_for_edge(
+        &self,
+        from_module_id: ModuleId,
+        edge: &EdgeWeight,
+    ) -> Option<&Dependency> {
+        return self
+            .get_parsed_module(from_module_id)?
+            .dependencies
+            .get(edge.dependency_index as usize);
+    }
+
+    /// The number of imports from one module to another.
+    /// In a collapsed graph this is the number of imports between the files of the two groups.
+    pub fn get_import_count(&self, from_path: &Path, to_path: &Path) -> Result<usize, &str> {
+        let graph_data = self
+            .graph_data
+            .as_ref()
+            .ok_or("Cannot call get_import_count before resolve_imports")?;
+
+        let from_module_id = self
+            .get_module_id_for_path(from_path)
+            .ok_or("Unable to get module for path")?;
+        let to_module_id = self
+            .get_module_id_for_path(to_path)
+            .ok_or("Unable to get module for path")?;
+
+        return Ok(graph_data
+            .graph
+            .edges_connecting(
+                graph_data.module_id_to_node_idx[from_module_id],
+                graph_data.module_id_to_node_idx[to_module_id],
+            )
+            .count());
+    }
+
+    pub fn get_module_ids(&self) -> impl Iterator<Item = ModuleId> + '_ {
+        return self
+            .dependency_graph_store
+            .modules()
+            .iter()
+            .map(|module| module.module_id);
+    }
+
+    pub fn get_module_id_for_path(&self, path: &Path) -> Option<ModuleId> {
+        return self
+            .dependency_graph_store
+            .try_get_module_for_path(path)
+            .map(|module| module.module_id);
+    }
+
+    pub fn get_file_module_ids_matching_glob(&self, glob: &GlobMatcher) -> Vec<ModuleId> {
+        let mut module_ids = self
+            .dependency_graph_store
+            .get_file_modules_matching_glob(glob)
+            .iter()
+            .map(|module| module.module_id)
+            .collect::<Vec<_>>();
+        // the files of a collapsed graph are only modules through their groups
+        module_ids.extend(
+            self.group_for_file
+                .iter()
+                .filter(|(file, _)| glob.is_match(file))
+                .filter_map(|(_, group)| self.get_module_id_for_path(group)),
+        );
+        module_ids.sort_by_key(|module_id| usize::from(*module_id));
+        module_ids.dedup();
+
+        return module_ids;
+    }
+
+    /// For a collapsed graph, the group module the file was collapsed into
+    pub fn get_group_for_file(&self, path: &Path) -> Option<&Path> {
+        return self.group_for_file.get(path).map(PathBuf::as_path);
+    }
 
-        let node_idx = graph_data.module_id_to_node_idx[module_id];
-        let dfe = DepthFirstExpansion::new(&graph_data.graph, direction, max_depth, node_idx);
+    /// Finds every module reachable from the given modules
+    pub fn get_reachable_module_ids(
+        &self,
+        roots: &[ModuleId],
+        direction: Direction,
+    ) -> Result<HashSet<ModuleId>, &str> {
+        let graph_data = self
+            .graph_data
+            .as_ref()
+            .ok_or("Cannot call get_reachable_module_ids before resolve_imports")?;
 
-        let paths = dfe
+        let dfe = DepthFirstExpansion::new(
+            &graph_data.graph,
+            direction,
+            roots
+                .iter()
+                .map(|module_id| graph_data.module_id_to_node_idx[*module_id])
+                .collect(),
+        );
+
+        return Ok(dfe
             .par_split()
-            .map(|node_idx| {
-                let module_id = graph_data.graph.node_weight(node_idx).unwrap();
-                self.dependency_graph_store
-                    .get_path_for_module(&self.dependency_graph_store.get_module_for_id(*module_id))
-            })
-            .fold(HashSet::new, |mut acc, path| {
-                acc.insert(path);
+            .map(|node_idx| *graph_data.graph.node_weight(node_idx).unwrap())
+            .fold(HashSet::new, |mut acc, module_id| {
+                acc.insert(module_id);
                 return acc;
             })
             .reduce(HashSet::new, |mut a, b| {
                 a.extend(b);
                 return a;
-            });
+            }));
+    }
+
+    pub fn get_path_for_module_id(&self, module_id: ModuleId) -> PathBuf {
+        return self
+            .dependency_graph_store
+            .get_path_for_module(&self.dependency_graph_store.get_module_for_id(module_id));
+    }
+
+    /// Every package.json within the search paths, sorted by path
+    pub fn get_package_jsons(&self) -> &[PackageJson] {
+        return &self.package_jsons;
+    }
+
+    /// The index into `get_package_jsons` of the package which contains the file - ie the nearest package.json
+    pub fn get_package_json_index_for_module_id(&self, module_id: ModuleId) -> Option<usize> {
+        if self.get_module_kind(module_id) != ModuleKind::File {
+            return None;
+        }
+
+        // the path itself is included for the directory modules of a collapsed graph - eg a package's own directory
+        return self
+            .get_path_for_module_id(module_id)
+            .ancestors()
+            .find_map(|directory| self.package_directory_to_index.get(directory).copied());
+    }
+
+    pub fn get_module_kind(&self, module_id: ModuleId) -> ModuleKind {
+        return self
+            .dependency_graph_store
+            .get_module_kind(&self.dependency_graph_store.get_module_for_id(module_id));
+    }
+
+    /// Finds the modules which import a file that is not in the graph - eg because it was deleted.
+    /// These are the relative imports which failed to resolve to the file, and the wildcard imports which match it.
+    pub fn get_importers_of_missing_path(&self, path: &Path) -> Result<Vec<ModuleId>, &str> {
+        let graph_data = self
+            .graph_data
+            .as_ref()
+            .ok_or("Cannot call get_importers_of_missing_path before resolve_imports")?;
+
+        let mut import_paths = vec![path.to_path_buf(), get_path_without_extension(path)];
+        if path.file_stem().is_some_and(|stem| stem == "index") {
+            // index files are importable via their parent folder name
+            import_paths.push(
+                path.parent()
+                    .expect("Path should not be the root")
+                    .to_path_buf(),
+            );
+        }
+
+        let mut module_ids = import_paths
+            .iter()
+            .filter_map(|import_path| graph_data.unresolved_imports.get(import_path))
+            .flatten()
+            .copied()
+            .chain(
+                graph_data
+                    .wildcard_imports
+                    .iter()
+                    .filter(|(glob, _)| glob.is_match(path))
+                    .map(|(_, module_id)| *module_id),
+            )
+            .collect::<Vec<_>>();
+        module_ids.sort_by_key(|module_id| usize::from(*module_id));
+        module_ids.dedup();
 
-        return Ok(paths);
+        return Ok(module_ids);
     }
 }
 
+// identifies a file written by `write_binary`, followed by the little-endian format version and then the graph as
+// MessagePack
+const BINARY_GRAPH_MAGIC: &[u8; 8] = b"ATHENAGR";
+// bump this whenever the binary graph format changes - including any of the types it contains, like `ParsedModule`
+const BINARY_GRAPH_VERSION: u32 = 1;
+
+// the adjacency is stored as an edge list as the graph's nodes are always the modules in module ID order
+#[derive(Serialize)]
+struct BinaryGraphRef<'a> {
+    store: &'a DependencyGraphStore,
+    package_jsons: &'a [PackageJson],
+    edges: Vec<(ModuleId, ModuleId, EdgeWeight)>,
+    parsed_modules: &'a [Option<ParsedModule>],
+    unresolved_imports: &'a HashMap<PathBuf, Vec<ModuleId>>,
+    wildcard_imports: Vec<(&'a str, ModuleId)>,
+}
+// the owned version of `BinaryGraphRef` - the fields must stay in the same order
+#[derive(Deserialize)]
+struct BinaryGraph {
+    store: DependencyGraphStore,
+    package_jsons: Vec<PackageJson>,
+    edges: Vec<(ModuleId, ModuleId, EdgeWeight)>,
+    parsed_modules: Vec<Option<ParsedModule>>,
+    unresolved_imports: HashMap<PathBuf, Vec<ModuleId>>,
+    wildcard_imports: Vec<(String, ModuleId)>,
+}
+
+// like webpack, the wildcard is allowed to match nested directories
+fn build_wildcard_glob(pattern: &str) -> Result<Glob, globset::Error> {
+    return GlobBuilder::new(pattern).literal_separator(false).build();
+}
+
+/// The resolved imports of a single file
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ResolvedImports {
+    /// The path and kind of the module each import resolved to - node modules are the import specifier
+    pub imports: Vec<(PathBuf, ModuleKind, EdgeWeight)>,
+    pub errors: Vec<Diagnostic>,
+    /// The absolute paths that relative imports failed to resolve to
+    pub unresolved_paths: Vec<PathBuf>,
+    /// The resolved glob of each wildcard import
+    pub wildcard_globs: Vec<String>,
+}
+
 struct ResolutionError {
-    module: Module,
+    rule: DiagnosticRule,
     message: String,
+    /// The location of the import in the module
+    location: SourceLocation,
+    /// The absolute path a relative import was expected to resolve to
+    unresolved_path: Option<PathBuf>,
+}
+
+/// Whether two dependency lists create the same edges - the bindings and locations of the imports don't affect the
+/// graph's structure
+fn is_same_dependency_list(a: &[Dependency], b: &[Dependency]) -> bool {
+    return a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| {
+            return a.specifier == b.specifier
+                && a.kind == b.kind
+                && a.is_type_only == b.is_type_only;
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestProject;
+
+    fn get_dependency_paths(graph: &DependencyGraph, path: &Path) -> Vec<PathBuf> {
+        let module_graph = graph.get_graph().unwrap();
+        let module_id = graph.get_module_id_for_path(path).unwrap();
+        let mut paths = module_graph
+            .neighbors(graph.get_node_for_module_id(module_id))
+            .map(|node_idx| graph.get_path_for_module_id(module_graph[node_idx]))
+            .collect::<Vec<_>>();
+        paths.sort();
+        return paths;
+    }
+
+    fn get_edge_paths(graph: &DependencyGraph) -> Vec<(PathBuf, PathBuf)> {
+        let module_graph = graph.get_graph().unwrap();
+        let mut edges = module_graph
+            .edge_references()
+            .map(|edge| {
+                return (
+                    graph.get_path_for_module_id(module_graph[edge.source()]),
+                    graph.get_path_for_module_id(module_graph[edge.target()]),
+                );
+            })
+            .collect::<Vec<_>>();
+        edges.sort();
+        return edges;
+    }
+
+    #[test]
+    fn wildcard_imports_resolve_to_every_matching_file() {
+        let project = TestProject::new(&[
+            (
+                "index.ts",
+                "import(`./locales/${lang}`);\nrequire('lodash/' + name);\nimport(`@scope/pkg/${name}`);",
+            ),
+            ("locales/en.ts", "export {};"),
+            ("locales/fr/index.ts", "export {};"),
+            ("other.ts", "export {};"),
+        ]);
+        let (graph, errors) = project.build_graph_with_errors(&[]);
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            get_dependency_paths(&graph, &project.path("index.ts")),
+            vec![
+                project.path("locales/en.ts"),
+                project.path("locales/fr/index.ts"),
+                PathBuf::from("@scope/pkg"),
+                PathBuf::from("lodash"),
+            ]
+        );
+    }
+
+    #[test]
+    fn wildcard_imports_which_cannot_be_resolved_are_reported() {
+        let project = TestProject::new(&[
+            (
+                "index.ts",
+                "import(`@scope/${name}`);\nimport(`./locales/${lang}.json`);\nimport(`./missing/${name}`);",
+            ),
+            ("locales/en.json", "{}"),
+        ]);
+        let (graph, errors) = project.build_graph_with_errors(&[]);
+
+        assert!(get_dependency_paths(&graph, &project.path("index.ts")).is_empty());
+        let errors = &errors[&project.path("index.ts")];
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| (error.rule, error.location.line))
+                .collect::<Vec<_>>(),
+            vec![
+                (DiagnosticRule::UnresolvedWildcardImport, 1),
+                (DiagnosticRule::UnresolvedWildcardImport, 2),
+                (DiagnosticRule::UnresolvedWildcardImport, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_graphs_round_trip() {
+        let project = TestProject::new(&[
+            (
+                "index.ts",
+                "import { a } from './a';\nimport React from 'react';\nimport './missing';",
+            ),
+            ("a.ts", "export const a = 1;\nimport(`./pages/${name}`);"),
+            ("pages/home.ts", "export {};"),
+        ]);
+        let graph = project.build_graph();
+        let graph_path = project.path("graph.bin");
+        graph.write_binary(&graph_path).unwrap();
+
+        let read_graph = DependencyGraph::read_binary(&graph_path);
+        assert_eq!(get_edge_paths(&read_graph), get_edge_paths(&graph));
+        let a_id = read_graph
+            .get_module_id_for_path(&project.path("a.ts"))
+            .unwrap();
+        assert_eq!(read_graph.get_parsed_module(a_id).unwrap().exports.len(), 1);
+        // the unresolved and wildcard imports are kept so that the importers of deleted files can still be found
+        let get_importer_paths = |path: &str| -> Vec<PathBuf> {
+            return read_graph
+                .get_importers_of_missing_path(&project.path(path))
+                .unwrap()
+                .into_iter()
+                .map(|module_id| read_graph.get_path_for_module_id(module_id))
+                .collect();
+        };
+        assert_eq!(
+            get_importer_paths("missing.ts"),
+            vec![project.path("index.ts")]
+        );
+        assert_eq!(
+            get_importer_paths("pages/about.ts"),
+            vec![project.path("a.ts")]
+        );
+    }
+
+    #[test]
+    fn binary_graph_write_failures_are_returned() {
+        let project = TestProject::new(&[("index.ts", "export {};")]);
+        let graph = project.build_graph();
+
+        let result = graph.write_binary(&project.path("missing/graph.bin"));
+        assert!(result.unwrap_err().starts_with("Unable to create graph"));
+    }
 }
@@ -1,6 +1,8 @@
+use globset::GlobMatcher;
+use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ffi::OsString,
     path::{Component, Path, PathBuf},
     str::FromStr,
@@ -8,15 +10,19 @@ use std::{
 
 use crate::{
     file_system::{extensions, is_declaration_file},
-    module::{Module, ModuleId, PathId},
+    module::{Module, ModuleId, ModuleKind, PathId},
     tsconfig::TSConfig,
 };
 
+#[derive(Serialize, Deserialize)]
 pub struct DependencyGraphStore {
     path_id_to_path: Vec<PathBuf>,
+    // this is the inverse of path_id_to_path, so it is rebuilt rather than saved
+    #[serde(skip)]
     path_to_path_id: HashMap<PathBuf, PathId>,
 
     pub module_id_to_module: Vec<Module>,
+    module_id_to_kind: Vec<ModuleKind>,
     // note - we use a hashmap here on purpose. If this were a Vec, we'd need to keep its length in sync with
     // path_id_to_path - which would double the number of resizes we need and substantially slow things down!
     path_id_to_module: HashMap<PathId, Module>,
@@ -45,6 +51,8 @@ impl DependencyGraphStore {
             })
             .collect::<Vec<_>>();
 
+        let module_id_to_kind = vec![ModuleKind::File; module_id_to_module.len()];
+
         let path_id_to_module = module_id_to_module
             .iter()
             .map(|module| (module.path_id, module.clone()))
@@ -54,6 +62,7 @@ impl DependencyGraphStore {
             path_id_to_path,
             path_to_path_id,
             module_id_to_module,
+            module_id_to_kind,
             path_id_to_module,
         };
 
@@ -61,6 +70,16 @@ impl DependencyGraphStore {
 
         return module_cache;
     }
+
+    /// Rebuilds the lookups which aren't saved, after the store has been deserialized
+    pub fn rebuild_path_lookup(&mut self) {
+        self.path_to_path_id = self
+            .path_id_to_path
+            .iter()
+            .enumerate()
+            .map(|(id, path)| (path.to_owned(), PathId::from(id)))
+            .collect();
+    }
 }
 
 // Path cache
@@ -94,8 +113,6 @@ impl DependencyGraphStore {
 // Module cache
 impl DependencyGraphStore {
     fn resolve_paths(&mut self, tsconfig: &TSConfig) {
-        let index_file_name = OsString::from_str("index").unwrap();
-
         // in order to save ourselves doing path resolution later we instead want to register every valid path for a
         // given module ahead-of-time. This front-loads the effort as much as possible to reduce duplicate transforms
         // done when resolving imported names.
@@ -107,41 +124,12 @@ impl DependencyGraphStore {
             .module_id_to_module
             .iter()
             // First we generate all possible non-relative import names for each module
-            .map(|module| {
-                let path = self.get_path_for_id(&module.path_id);
-
-                let mut extra_paths = vec![];
-
-                if let Some(base_url) = &tsconfig.base_url {
-                    if let Ok(path_without_base) = path.strip_prefix(base_url) {
-                        extra_paths.push((path_without_base.to_path_buf(), module.clone()));
-                    }
-                }
-
-                if path.file_stem().unwrap() == index_file_name {
-                    // index files are importable via their parent folder name
-                    extra_paths.push((
-                        path.parent()
-                            .expect("Should not be the parent")
-                            .to_path_buf(),
-                        module.clone(),
-                    ))
-                }
-
-                // add extension-less variants for each of the extra paths
-                for i in 0..extra_paths.len() {
-                    let (extra_path, _) = &extra_paths[i];
-                    extra_paths.push(
-                        // extension-less version which is the standard way to import things
-                        (get_path_without_extension(&extra_path), module.clone()),
-                    );
-                }
-                // and an extension-less variant for the base path
-                extra_paths.push((get_path_without_extension(&path), module.clone()));
-
-                return extra_paths;
+            .flat_map(|module| {
+                return self
+                    .get_file_aliases(&self.get_path_for_module(module), tsconfig)
+                    .into_iter()
+                    .map(|path| (path, *module));
             })
-            .flatten()
             // then we group the modules by path
             .fold(HashMap::new(), |mut acc, (path, module)| {
                 acc.entry(path).or_insert(Vec::new()).push(module);
@@ -159,25 +147,7 @@ impl DependencyGraphStore {
                     _ => {
                         // Note: sorting so highest precedence is first
                         let mut modules = modules.clone();
-                        modules.sort_by(|a, b| -> Ordering {
-                            let a_path = self.get_path_for_module(&a);
-                            let b_path = self.get_path_for_module(&b);
-
-                            // prefer /path/to/foo.ts over /path/to/foo/index.ts
-                            if a_path.file_stem().unwrap() == index_file_name
-                                && b_path.file_stem().unwrap() != index_file_name
-                            {
-                                return Ordering::Greater;
-                            }
-                            if a_path.file_stem().unwrap() != index_file_name
-                                && b_path.file_stem().unwrap() == index_file_name
-                            {
-                                return Ordering::Less;
-                            }
-
-                            return get_extension_precedence(&b_path)
-                                .cmp(&get_extension_precedence(&a_path));
-                        });
+                        modules.sort_by(|a, b| self.compare_file_precedence(a, b));
 
                         return (path.to_owned(), modules[0]);
                     }
@@ -191,12 +161,156 @@ impl DependencyGraphStore {
         }
     }
 
+    /// Every path other than its own that a file can be imported by - eg `/src/foo` for `/src/foo.ts`
+    fn get_file_aliases(&self, path: &Path, tsconfig: &TSConfig) -> Vec<PathBuf> {
+        let index_file_name = OsString::from_str("index").unwrap();
+        let mut extra_paths = vec![];
+
+        if let Some(base_url) = &tsconfig.base_url {
+            if let Ok(path_without_base) = path.strip_prefix(base_url) {
+                extra_paths.push(path_without_base.to_path_buf());
+            }
+        }
+
+        if path.file_stem().unwrap() == index_file_name {
+            // index files are importable via their parent folder name
+            extra_paths.push(
+                path.parent()
+                    .expect("Should not be the parent")
+                    .to_path_buf(),
+            )
+        }
+
+        // add extension-less variants for each of the extra paths
+        for i in 0..extra_paths.len() {
+            // extension-less version which is the standard way to import things
+            extra_paths.push(get_path_without_extension(&extra_paths[i]));
+        }
+        // and an extension-less variant for the base path
+        extra_paths.push(get_path_without_extension(path));
+
+        return extra_paths;
+    }
+
+    /// Orders two files which can be imported by the same path, so that the file the path resolves to is first
+    fn compare_file_precedence(&self, a: &Module, b: &Module) -> Ordering {
+        let index_file_name = OsString::from_str("index").unwrap();
+        let a_path = self.get_path_for_module(a);
+        let b_path = self.get_path_for_module(b);
+
+        // prefer /path/to/foo.ts over /path/to/foo/index.ts
+        if a_path.file_stem().unwrap() == index_file_name
+            && b_path.file_stem().unwrap() != index_file_name
+        {
+            return Ordering::Greater;
+        }
+        if a_path.file_stem().unwrap() != index_file_name
+            && b_path.file_stem().unwrap() == index_file_name
+        {
+            return Ordering::Less;
+        }
+
+        return get_extension_precedence(&b_path).cmp(&get_extension_precedence(&a_path));
+    }
+
+    /// Registers a file which was created after the store was built.
+    /// Returns the new module, and the modules which previously resolved from any of the paths that now resolve to the
+    /// new file - eg `/src/foo/index.ts` when `/src/foo.ts` is added.
+    pub fn add_file(&mut self, path: &Path, tsconfig: &TSConfig) -> (Module, Vec<Module>) {
+        let module = Module {
+            path_id: self.get_id_for_path(path),
+            module_id: ModuleId::from(self.module_id_to_module.len()),
+        };
+        self.module_id_to_module.push(module);
+        self.module_id_to_kind.push(ModuleKind::File);
+
+        let mut displaced_modules = vec![];
+        displaced_modules.extend(self.path_id_to_module.insert(module.path_id, module));
+        for alias in self.get_file_aliases(path, tsconfig) {
+            let alias_id = self.get_id_for_path(&alias);
+            match self.path_id_to_module.get(&alias_id).copied() {
+                // a file with a higher precedence keeps the path
+                Some(existing)
+                    if self.get_module_kind(&existing) == ModuleKind::File
+                        && self.compare_file_precedence(&existing, &module) == Ordering::Less => {}
+                existing => {
+                    displaced_modules.extend(existing);
+                    self.path_id_to_module.insert(alias_id, module);
+                }
+            }
+        }
+        displaced_modules.sort_by_key(|module| usize::from(module.module_id));
+        displaced_modules.dedup();
+
+        return (module, displaced_modules);
+    }
+
+    /// Removes a module, and returns the paths which resolved to it.
+    /// The last module is moved into the removed module's ID so that the IDs stay contiguous - which is the same way
+    /// petgraph removes nodes, so a graph with a node per module in module ID order stays in sync.
+    pub fn remove_module(&mut self, module: &Module) -> Vec<PathBuf> {
+        let removed_path_ids = self
+            .path_id_to_module
+            .iter()
+            .filter(|(_, path_module)| *path_module == module)
+            .map(|(path_id, _)| *path_id)
+            .collect::<Vec<_>>();
+        for path_id in removed_path_ids.iter() {
+            self.path_id_to_module.remove(path_id);
+        }
+
+        let removed_id = usize::from(module.module_id);
+        let last_id = self.module_id_to_module.len() - 1;
+        self.module_id_to_module.swap_remove(removed_id);
+        self.module_id_to_kind.swap_remove(removed_id);
+        if removed_id != last_id {
+            let last_module_id = ModuleId::from(last_id);
+            self.module_id_to_module[removed_id].module_id = module.module_id;
+            for path_module in self.path_id_to_module.values_mut() {
+                if path_module.module_id == last_module_id {
+                    path_module.module_id = module.module_id;
+                }
+            }
+        }
+
+        return removed_path_ids
+            .iter()
+            .map(|path_id| self.get_path_for_id(path_id))
+            .collect();
+    }
+
+    /// Points paths which no longer resolve to a module at the next best file - eg `/src/foo` resolves to
+    /// `/src/foo/index.ts` once `/src/foo.ts` is removed
+    pub fn reassign_paths(&mut self, paths: &[PathBuf], tsconfig: &TSConfig) {
+        let paths = paths.iter().collect::<HashSet<_>>();
+        let mut path_to_potential_modules: HashMap<PathBuf, Vec<Module>> = HashMap::new();
+        for module in self.module_id_to_module.iter() {
+            if self.get_module_kind(module) != ModuleKind::File {
+                continue;
+            }
+            for alias in self.get_file_aliases(&self.get_path_for_module(module), tsconfig) {
+                if paths.contains(&alias) {
+                    path_to_potential_modules
+                        .entry(alias)
+                        .or_default()
+                        .push(*module);
+                }
+            }
+        }
+
+        for (path, mut modules) in path_to_potential_modules {
+            modules.sort_by(|a, b| self.compare_file_precedence(a, b));
+            let path_id = self.get_id_for_path(&path);
+            self.path_id_to_module.insert(path_id, modules[0]);
+        }
+    }
+
     pub fn add_node_module(&mut self, path: &Path) -> Module {
         // we just want the top-level node module name, not the deep path
         // eg we don't care that `A -> mod/foo` and `B -> mod/bar`, we just care that `(A, B) -> mod`
         let module_name = {
             let mut components = path.components();
-            if path.starts_with("@") {
+            if path.to_string_lossy().starts_with('@') {
                 // is an @-scoped name, which always has two parts
                 match components.next().expect("Expected a first part") {
                     Component::Normal(first) => {
@@ -252,11 +366,14 @@ impl DependencyGraphStore {
             return module.clone();
         }
 
+        // files created after construction are registered by `add_file`, so the only modules created here are node
+        // modules
         let new_id = self.module_id_to_module.len();
         self.module_id_to_module.push(Module {
             path_id,
             module_id: ModuleId::from(new_id),
         });
+        self.module_id_to_kind.push(ModuleKind::NodeModule);
         let module = &self.module_id_to_module[new_id];
 
         self.path_id_to_module.insert(path_id, module.clone());
@@ -267,6 +384,28 @@ impl DependencyGraphStore {
     pub fn get_module_for_id(&self, id: ModuleId) -> Module {
         return self.module_id_to_module[id].clone();
     }
+
+    pub fn get_module_kind(&self, module: &Module) -> ModuleKind {
+        return self.module_id_to_kind[module.module_id];
+    }
+
+    /// Finds every file module which has a registered path matching the glob.
+    /// Because every importable variant of a module's path is registered, this will match extension-less and index
+    /// paths too - eg `/src/plugins/*` will match both `/src/plugins/foo.ts` and `/src/plugins/bar/index.ts`
+    pub fn get_file_modules_matching_glob(&self, glob: &GlobMatcher) -> Vec<Module> {
+        let mut matched_modules = self
+            .path_to_path_id
+            .iter()
+            .filter(|(path, _)| glob.is_match(path))
+            .filter_map(|(_, path_id)| self.path_id_to_module.get(path_id))
+            .filter(|module| self.get_module_kind(module) == ModuleKind::File)
+            .copied()
+            .collect::<Vec<_>>();
+        matched_modules.sort_by_key(|module| usize::from(module.module_id));
+        matched_modules.dedup();
+
+        return matched_modules;
+    }
 }
 
 fn get_extension_precedence(path: &Path) -> u8 {
@@ -299,7 +438,7 @@ fn get_extension_precedence(path: &Path) -> u8 {
     };
 }
 
-fn get_path_without_extension(path: &Path) -> PathBuf {
+pub fn get_path_without_extension(path: &Path) -> PathBuf {
     if is_declaration_file(&path) {
         // you don't include the `.d`
         return path.with_extension("").with_extension("");
@@ -1,38 +1,36 @@
 use std::sync::Arc;
 
-use parking_lot::RwLock;
 use petgraph::{graph::NodeIndex, Direction};
 use spliter::Spliterator;
 
-use crate::module::ModuleGraph;
-
-struct StackItem {
-    node_idx: NodeIndex,
-    depth: u32,
-}
+use crate::{atomic_bit_set::AtomicBitSet, module::ModuleGraph};
 
+/// A traversal which finds every node reachable from the roots, in no particular order.
+/// Splits share the set of seen nodes, so each node is yielded exactly once across all splits.
+/// Use `BreadthFirstExpansion` when the depth of each node matters.
 pub struct DepthFirstExpansion<'a> {
     direction: Direction,
     graph: &'a ModuleGraph,
-    max_depth: u32,
-    stack: Vec<StackItem>,
-    seen_nodes: Arc<RwLock<Vec<bool>>>,
+    stack: Vec<NodeIndex>,
+    // nodes are marked when pushed rather than when popped - the test-and-set decides which split claims a node, so
+    // two splits can never both expand it
+    seen_nodes: Arc<AtomicBitSet>,
 }
 
 impl<'a> DepthFirstExpansion<'a> {
-    /// Create a new search with the given starting point.
-    pub fn new(
-        graph: &'a ModuleGraph,
-        direction: Direction,
-        max_depth: u32,
-        node_idx: NodeIndex,
-    ) -> Self {
+    /// Create a new search with the given starting points.
+    pub fn new(graph: &'a ModuleGraph, direction: Direction, roots: Vec<NodeIndex>) -> Self {
+        let seen_nodes = AtomicBitSet::new(graph.node_count());
+        let stack = roots
+            .into_iter()
+            .filter(|node_idx| !seen_nodes.test_and_set(node_idx.index()))
+            .collect();
+
         return Self {
             direction,
             graph,
-            max_depth,
-            seen_nodes: Arc::new(RwLock::new(vec![false; graph.node_count()])),
-            stack: vec![StackItem { node_idx, depth: 0 }],
+            stack,
+            seen_nodes: Arc::new(seen_nodes),
         };
     }
 }
@@ -41,32 +39,15 @@ impl<'a> Iterator for DepthFirstExpansion<'a> {
     type Item = NodeIndex;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(item) = self.stack.pop() {
-            if self.seen_nodes.read()[item.node_idx.index()] {
-                // already seen - don't expand
-                return Some(item.node_idx);
-            }
-            self.seen_nodes.write().insert(item.node_idx.index(), true);
+        let node_idx = self.stack.pop()?;
 
-            if self.max_depth > 0 && item.depth >= self.max_depth {
-                // hit max depth - don't expand further
-                return Some(item.node_idx);
+        for neighbor in self.graph.neighbors_directed(node_idx, self.direction) {
+            if !self.seen_nodes.test_and_set(neighbor.index()) {
+                self.stack.push(neighbor);
             }
-
-            let new_depth = item.depth + 1;
-            self.stack.extend(
-                self.graph
-                    .neighbors_directed(item.node_idx, self.direction)
-                    .map(|neighbor| StackItem {
-                        node_idx: neighbor,
-                        depth: new_depth,
-                    }),
-            );
-            return Some(item.node_idx);
         }
 
-        // the None return tells the iterator the iteration is finsihed to exit
-        return None;
+        return Some(node_idx);
     }
 }
 
@@ -78,8 +59,7 @@ impl<'a> Spliterator for DepthFirstExpansion<'a> {
             let stack = self.stack.split_off(len / 2);
             return Some(Self {
                 direction: self.direction,
-                graph: &self.graph,
-                max_depth: self.max_depth,
+                graph: self.graph,
                 seen_nodes: self.seen_nodes.clone(),
                 stack,
             });
@@ -88,3 +68,161 @@ impl<'a> Spliterator for DepthFirstExpansion<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, time::Instant};
+
+    use petgraph::{graph::NodeIndex, Direction};
+    use rayon::prelude::*;
+    use spliter::ParallelSpliterator;
+
+    use super::DepthFirstExpansion;
+    use crate::{
+        breadth_first_expansion::BreadthFirstExpansion,
+        module::{DependencyKind, EdgeWeight, ModuleGraph, ModuleId},
+    };
+
+    /// A small xorshift generator so the graphs are random but reproducible from the seed
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, max: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            return (self.0 % max as u64) as usize;
+        }
+    }
+
+    /// Builds a graph with random edges, including cycles, self loops and parallel edges
+    fn random_graph(rng: &mut Rng, node_count: usize, edge_count: usize) -> ModuleGraph {
+        let mut graph = ModuleGraph::with_capacity(node_count, edge_count);
+        for idx in 0..node_count {
+            graph.add_node(ModuleId::from(idx));
+        }
+        for _ in 0..edge_count {
+            graph.add_edge(
+                NodeIndex::new(rng.next(node_count)),
+                NodeIndex::new(rng.next(node_count)),
+                EdgeWeight {
+                    kind: DependencyKind::Static,
+                    dependency_index: 0,
+                    is_type_only: false,
+                },
+            );
+        }
+        return graph;
+    }
+
+    /// The plain sequential traversal the expansion has to agree with
+    fn reference_reachable(
+        graph: &ModuleGraph,
+        direction: Direction,
+        roots: &[NodeIndex],
+    ) -> HashSet<NodeIndex> {
+        let mut seen = HashSet::new();
+        let mut stack = roots.to_vec();
+        while let Some(node_idx) = stack.pop() {
+            if seen.insert(node_idx) {
+                stack.extend(graph.neighbors_directed(node_idx, direction));
+            }
+        }
+        return seen;
+    }
+
+    fn random_roots(rng: &mut Rng, node_count: usize) -> Vec<NodeIndex> {
+        // roots can repeat - the expansion must still yield them once
+        return (0..1 + rng.next(4))
+            .map(|_| NodeIndex::new(rng.next(node_count)))
+            .collect();
+    }
+
+    #[test]
+    fn sequential_matches_reference() {
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        for _ in 0..200 {
+            let node_count = 1 + rng.next(200);
+            let edge_count = rng.next(node_count * 3);
+            let graph = random_graph(&mut rng, node_count, edge_count);
+            let roots = random_roots(&mut rng, node_count);
+            for direction in [Direction::Outgoing, Direction::Incoming] {
+                let yielded: Vec<NodeIndex> =
+                    DepthFirstExpansion::new(&graph, direction, roots.clone()).collect();
+                let unique: HashSet<NodeIndex> = yielded.iter().copied().collect();
+
+                assert_eq!(yielded.len(), unique.len(), "a node was yielded twice");
+                assert_eq!(unique, reference_reachable(&graph, direction, &roots));
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_matches_reference() {
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+        for _ in 0..200 {
+            let node_count = 1 + rng.next(2_000);
+            let edge_count = rng.next(node_count * 4);
+            let graph = random_graph(&mut rng, node_count, edge_count);
+            let roots = random_roots(&mut rng, node_count);
+            for direction in [Direction::Outgoing, Direction::Incoming] {
+                let yielded: Vec<NodeIndex> =
+                    DepthFirstExpansion::new(&graph, direction, roots.clone())
+                        .par_split()
+                        .collect();
+                let unique: HashSet<NodeIndex> = yielded.iter().copied().collect();
+
+                assert_eq!(yielded.len(), unique.len(), "a node was yielded twice");
+                assert_eq!(unique, reference_reachable(&graph, direction, &roots));
+            }
+        }
+    }
+
+    // cargo test --release -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn time_large_graph() {
+        let node_count = 250_000;
+        let mut rng = Rng(0xd1b54a32d192ed03);
+        let graph = random_graph(&mut rng, node_count, node_count * 6);
+        let roots = vec![NodeIndex::new(0)];
+
+        let start = Instant::now();
+        let expected = reference_reachable(&graph, Direction::Outgoing, &roots);
+        println!(
+            "reference: {} nodes in {:?}",
+            expected.len(),
+            start.elapsed()
+        );
+
+        let start = Instant::now();
+        let sequential =
+            DepthFirstExpansion::new(&graph, Direction::Outgoing, roots.clone()).count();
+        println!("sequential: {} nodes in {:?}", sequential, start.elapsed());
+
+        // the breadth first traversal used for depths and trees, for comparison
+        let start = Instant::now();
+        let breadth_first =
+            BreadthFirstExpansion::new(&graph, Direction::Outgoing, 0, roots.clone()).count();
+        println!(
+            "breadth first: {} nodes in {:?}",
+            breadth_first,
+            start.elapsed()
+        );
+
+        let start = Instant::now();
+        let parallel: HashSet<NodeIndex> =
+            DepthFirstExpansion::new(&graph, Direction::Outgoing, roots)
+                .par_split()
+                .collect();
+        println!(
+            "parallel: {} nodes in {:?}",
+            parallel.len(),
+            start.elapsed()
+        );
+
+        assert_eq!(sequential, expected.len());
+        assert_eq!(breadth_first, expected.len());
+        assert_eq!(parallel, expected);
+    }
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+use crate::import_visitor::SourceLocation;
+
+/// The kinds of problem found while extracting or resolving a file's imports
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticRule {
+    /// An import or call pattern whose specifier cannot be statically determined
+    DynamicImport,
+    /// A call pattern with a missing or non-string specifier argument
+    InvalidImportArgument,
+    /// A relative import which does not point at a file in the search paths
+    UnresolvedImport,
+    /// A wildcard import which does not match any file in the search paths
+    UnresolvedWildcardImport,
+}
+impl DiagnosticRule {
+    /// A stable identifier for the rule, eg for code scanning tools
+    pub fn id(&self) -> &'static str {
+        return match self {
+            DiagnosticRule::DynamicImport => "dynamic-import",
+            DiagnosticRule::InvalidImportArgument => "invalid-import-argument",
+            DiagnosticRule::UnresolvedImport => "unresolved-import",
+            DiagnosticRule::UnresolvedWildcardImport => "unresolved-wildcard-import",
+        };
+    }
+
+    pub fn description(&self) -> &'static str {
+        return match self {
+            DiagnosticRule::DynamicImport => {
+                "The import specifier is not static, so its dependency cannot be resolved"
+            }
+            DiagnosticRule::InvalidImportArgument => {
+                "The import call does not have a string specifier argument"
+            }
+            DiagnosticRule::UnresolvedImport => {
+                "The relative import does not resolve to an existing module"
+            }
+            DiagnosticRule::UnresolvedWildcardImport => {
+                "The wildcard import does not resolve to any existing modules"
+            }
+        };
+    }
+}
+
+/// A problem with a file found while extracting or resolving its imports
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub rule: DiagnosticRule,
+    pub message: String,
+    pub location: SourceLocation,
+}
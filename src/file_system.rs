@@ -1,12 +1,19 @@
 use clean_path::Clean;
-use ignore::{types::TypesBuilder, WalkBuilder, WalkState};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::{
+    types::{Types, TypesBuilder},
+    WalkBuilder, WalkState,
+};
 use parking_lot::Mutex;
 use std::{
     path::{Path, PathBuf},
     str::FromStr,
 };
 
-pub fn get_files(paths: &Vec<PathBuf>) -> Vec<PathBuf> {
+use crate::output::Output;
+
+/// The file types which are parsed
+fn get_source_file_types() -> Types {
     let mut types_builder = TypesBuilder::new();
     types_builder
         .add("typescript", "*.{cts,mts,ts,tsx}")
@@ -16,15 +23,17 @@ pub fn get_files(paths: &Vec<PathBuf>) -> Vec<PathBuf> {
         .add("javascript", "*.{cjs,mjs,js,jsx}")
         .expect("Invalid glob");
     types_builder.select("javascript");
-    let types = types_builder.build().expect("Unable to build types");
+    return types_builder.build().expect("Unable to build types");
+}
 
+pub fn get_files(paths: &Vec<PathBuf>, output: &Output) -> Vec<PathBuf> {
     let mut walk_builder = WalkBuilder::new(paths[0].to_owned());
     if paths.len() > 1 {
         for path in paths.iter().skip(1) {
             walk_builder.add(path.to_owned());
         }
     }
-    walk_builder.types(types);
+    walk_builder.types(get_source_file_types());
 
     // no need for an Arc here because we know the closures will never outlive the function
     let files = Mutex::new(vec![]);
@@ -64,7 +73,7 @@ pub fn get_files(paths: &Vec<PathBuf>) -> Vec<PathBuf> {
                         // ignore non-file entries
                     }
                 },
-                Err(err) => println!("ERROR: {}", err),
+                Err(err) => output.report_warning(err.to_string()),
             };
             return WalkState::Continue;
         });
@@ -73,8 +82,45 @@ pub fn get_files(paths: &Vec<PathBuf>) -> Vec<PathBuf> {
     return files.into_inner();
 }
 
+/// Whether `get_files` would find the given file or directory within the search paths.
+/// Each directory between the search path and the path is listed in turn so that exactly the same ignore rules apply,
+/// which is only fast enough for checking a handful of paths.
+pub fn is_in_search_paths(search_paths: &[PathBuf], path: &Path) -> bool {
+    let search_path = match search_paths
+        .iter()
+        .find(|search_path| path.starts_with(search_path))
+    {
+        Some(search_path) => search_path,
+        None => return false,
+    };
+
+    let mut directory = search_path.to_path_buf();
+    for component in path.strip_prefix(search_path).unwrap().components() {
+        let child = directory.join(component);
+        let is_found = WalkBuilder::new(&directory)
+            .max_depth(Some(1))
+            .types(get_source_file_types())
+            .build()
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.path() == child);
+        if !is_found {
+            return false;
+        }
+        directory = child;
+    }
+
+    return true;
+}
+
 pub fn is_declaration_file(path: &Path) -> bool {
-    return path.ends_with(".d.ts") || path.ends_with(".d.mts") || path.ends_with(".d.cts");
+    // `Path::ends_with` compares whole components, so the file name has to be checked as a string
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name.to_string_lossy(),
+        None => return false,
+    };
+    return file_name.ends_with(".d.ts")
+        || file_name.ends_with(".d.mts")
+        || file_name.ends_with(".d.cts");
 }
 
 /// Ensures a path exists and converts it to an absolute representation
@@ -84,6 +130,67 @@ pub fn path_parser_absolute(path: &str) -> Result<PathBuf, std::io::Error> {
         .canonicalize();
 }
 
+/// Converts a path to an absolute representation, canonicalizing it if it exists.
+/// Unlike `path_parser_absolute` the path does not have to exist, as the modules of a collapsed graph are not always
+/// real paths - eg the group `NAME` from `--collapse-group NAME=GLOB`.
+pub fn path_parser_module(path: &str) -> Result<PathBuf, std::io::Error> {
+    let path = PathBuf::from(path);
+    return match path.canonicalize() {
+        Ok(path) => Ok(path),
+        Err(_) => Ok(std::env::current_dir()?.join(path).clean()),
+    };
+}
+
+/// The size of a file in bytes, or 0 if it is not a file - eg the directory modules of a collapsed graph, whose size is
+/// not the size of their files
+pub fn get_file_size(path: &Path) -> u64 {
+    return std::fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map_or(0, |metadata| metadata.len());
+}
+
+/// Reads a newline-separated list of paths from a file, or from stdin if the path is `-`
+pub fn read_path_list(path: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let contents = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path)?
+    };
+
+    return Ok(contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect());
+}
+
+/// Builds a matcher for the given globs.
+/// All file paths are absolute, so relative globs are treated as being relative to the current working directory
+pub fn build_glob_set(globs: &[String]) -> Result<GlobSet, globset::Error> {
+    let current_dir = std::env::current_dir().expect("Unable to read the current directory");
+    return build_glob_set_relative_to(globs, &current_dir);
+}
+
+/// Builds a matcher for the given globs, treating relative globs as being relative to the given directory
+pub fn build_glob_set_relative_to(
+    globs: &[String],
+    directory: &Path,
+) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        let glob = if Path::new(glob).is_absolute() {
+            glob.to_owned()
+        } else {
+            directory.join(glob).to_string_lossy().to_string()
+        };
+        builder.add(Glob::new(&glob)?);
+    }
+
+    return builder.build();
+}
+
 pub mod extensions {
     // TS extensions
     pub const TS: &str = "ts";
@@ -0,0 +1,164 @@
+use clean_path::Clean;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+fn run_git(args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .expect("Unable to run git - is it installed?");
+    if !output.status.success() {
+        panic!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    return String::from_utf8_lossy(&output.stdout).to_string();
+}
+
+/// The absolute path of the root of the repository containing the current working directory
+pub fn get_repository_root() -> PathBuf {
+    return PathBuf::from(run_git(&["rev-parse", "--show-toplevel"]).trim());
+}
+
+/// Like `get_repository_root`, but `None` when git is not installed or the current directory is not in a repository
+pub fn try_get_repository_root() -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    return Some(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ));
+}
+
+/// Lists the files changed within a revision range (eg `main...HEAD`).
+/// Renamed files are reported as both their old and new paths.
+pub fn get_changed_files(range: &str) -> Vec<PathBuf> {
+    // -z stops git from quoting paths which contain spaces or special characters
+    let output = run_git(&["diff", "--name-status", "-M", "-z", range]);
+    return parse_changed_files(&output, &get_repository_root());
+}
+
+/// Parses the output of `git diff --name-only` or `git diff --name-status`, with or without `-z`, into absolute paths.
+/// git always reports paths relative to the root of the repository.
+pub fn parse_changed_files(output: &str, repository_root: &Path) -> Vec<PathBuf> {
+    let relative_paths = if output.contains('\0') {
+        parse_nul_separated(output)
+    } else {
+        parse_lines(output)
+    };
+    let mut paths = relative_paths
+        .iter()
+        .map(|path| repository_root.join(path).clean())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+
+    return paths;
+}
+
+/// With -z every field is terminated by a NUL, and renames and copies are followed by both of their paths
+fn parse_nul_separated(output: &str) -> Vec<String> {
+    let fields = output
+        .split('\0')
+        .filter(|field| !field.is_empty())
+        .collect::<Vec<_>>();
+    if !fields.first().is_some_and(|field| is_status(field)) {
+        // --name-only
+        return fields.into_iter().map(String::from).collect();
+    }
+
+    let mut paths = vec![];
+    let mut fields = fields.into_iter();
+    while let Some(status) = fields.next() {
+        let path_count = if status.starts_with('R') || status.starts_with('C') {
+            2
+        } else {
+            1
+        };
+        paths.extend(fields.by_ref().take(path_count).map(String::from));
+    }
+    return paths;
+}
+
+fn parse_lines(output: &str) -> Vec<String> {
+    return output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .flat_map(|line| {
+            let mut fields = line.split('\t').collect::<Vec<_>>();
+            if fields.len() > 1 {
+                // --name-status - the first field is the status, eg `M`, `D` or `R100`
+                fields.remove(0);
+            }
+            return fields;
+        })
+        .map(unquote_path)
+        .collect();
+}
+
+/// eg `M`, `D` or `R100`
+fn is_status(field: &str) -> bool {
+    let mut chars = field.chars();
+    return chars
+        .next()
+        .is_some_and(|status| "ACDMRTUXB".contains(status))
+        && chars.all(|char| char.is_ascii_digit());
+}
+
+/// Without -z git wraps paths containing special characters in quotes and escapes them like a C string - eg
+/// `"caf\303\251.ts"` for `café.ts`
+fn unquote_path(field: &str) -> String {
+    let quoted = match field
+        .strip_prefix('"')
+        .and_then(|field| field.strip_suffix('"'))
+    {
+        Some(quoted) => quoted.as_bytes(),
+        None => return field.to_string(),
+    };
+
+    let mut bytes = Vec::with_capacity(quoted.len());
+    let mut idx = 0;
+    while idx < quoted.len() {
+        if quoted[idx] != b'\\' || idx + 1 == quoted.len() {
+            bytes.push(quoted[idx]);
+            idx += 1;
+            continue;
+        }
+
+        let escaped = quoted[idx + 1];
+        idx += 2;
+        match escaped {
+            b'0'..=b'7' => {
+                // an octal byte - always three digits
+                let mut value = escaped - b'0';
+                for _ in 0..2 {
+                    if idx < quoted.len() && (b'0'..=b'7').contains(&quoted[idx]) {
+                        value = value.wrapping_mul(8) + (quoted[idx] - b'0');
+                        idx += 1;
+                    }
+                }
+                bytes.push(value);
+            }
+            b'a' => bytes.push(0x07),
+            b'b' => bytes.push(0x08),
+            b'f' => bytes.push(0x0c),
+            b'n' => bytes.push(b'\n'),
+            b'r' => bytes.push(b'\r'),
+            b't' => bytes.push(b'\t'),
+            b'v' => bytes.push(0x0b),
+            other => bytes.push(other),
+        }
+    }
+
+    return String::from_utf8_lossy(&bytes).to_string();
+}
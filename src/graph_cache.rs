@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    call_pattern::CallPattern, dependency_graph::ResolvedImports, diagnostic::Diagnostic,
+    import_visitor::ParsedModule, package_json::PackageJson, tsconfig::TSConfig,
+};
+
+// bump this whenever the cached data changes shape or meaning so that old caches are discarded
+const CACHE_VERSION: u32 = 1;
+
+/// Identifies a version of a file without reading it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    modified_secs: u64,
+    modified_nanos: u32,
+    size: u64,
+}
+impl FileFingerprint {
+    pub fn new(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        return Some(Self {
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+            size: metadata.len(),
+        });
+    }
+}
+
+/// Everything extracted from a single file
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedFile {
+    pub fingerprint: FileFingerprint,
+    pub parsed_module: ParsedModule,
+    /// The errors found while parsing the file
+    pub errors: Vec<Diagnostic>,
+    /// `None` if the resolution inputs have changed since the file was resolved
+    pub resolved_imports: Option<ResolvedImports>,
+}
+
+/// The parsed and resolved imports of every file from a previous run, so that only the changed files need to be
+/// parsed again. Resolution depends on every file, so the resolved imports are only reused while the tsconfig, the
+/// package.jsons and the set of files are unchanged - otherwise every file is resolved again.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphCache {
+    version: u32,
+    /// Changes whenever the options which affect parsing change
+    parse_fingerprint: u64,
+    /// Changes whenever the inputs to import resolution change
+    resolution_fingerprint: u64,
+    files: HashMap<PathBuf, CachedFile>,
+}
+impl GraphCache {
+    pub fn new(parse_fingerprint: u64, resolution_fingerprint: u64) -> Self {
+        return Self {
+            version: CACHE_VERSION,
+            parse_fingerprint,
+            resolution_fingerprint,
+            files: HashMap::new(),
+        };
+    }
+
+    /// The cache file for a set of search paths and tsconfig, so that different projects can share a cache directory
+    pub fn get_path(cache_dir: &Path, search_paths: &[PathBuf], tsconfig_path: &Path) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        search_paths.hash(&mut hasher);
+        tsconfig_path.hash(&mut hasher);
+        return cache_dir.join(format!("graph-{:016x}.json", hasher.finish()));
+    }
+
+    /// Reads the cache, discarding whatever is no longer valid - a missing or unreadable cache is empty
+    pub fn read(path: &Path, parse_fingerprint: u64) -> Self {
+        let cache: Option<Self> = File::open(path)
+            .ok()
+            .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok());
+        return match cache {
+            Some(cache)
+                if cache.version == CACHE_VERSION
+                    && cache.parse_fingerprint == parse_fingerprint =>
+            {
+                cache
+            }
+            _ => Self::new(parse_fingerprint, 0),
+        };
+    }
+
+    /// Discards the resolved imports if they were resolved with different inputs
+    pub fn set_resolution_fingerprint(&mut self, resolution_fingerprint: u64) {
+        if self.resolution_fingerprint != resolution_fingerprint {
+            for file in self.files.values_mut() {
+                file.resolved_imports = None;
+            }
+            self.resolution_fingerprint = resolution_fingerprint;
+        }
+    }
+
+    /// Gets the cached data for a file if it hasn't changed since it was cached
+    pub fn get_file(&self, path: &Path, fingerprint: &FileFingerprint) -> Option<&CachedFile> {
+        return self
+            .files
+            .get(path)
+            .filter(|file| file.fingerprint == *fingerprint);
+    }
+
+    /// Counts the given files which are unchanged since they were cached
+    pub fn count_unchanged_files(&self, files: &HashMap<PathBuf, CachedFile>) -> usize {
+        return files
+            .iter()
+            .filter(|(path, file)| self.get_file(path, &file.fingerprint).is_some())
+            .count();
+    }
+
+    /// Takes the resolved imports of every file which are still valid
+    pub fn take_resolved_imports(&mut self) -> HashMap<PathBuf, ResolvedImports> {
+        return self
+            .files
+            .iter_mut()
+            .filter_map(|(path, file)| Some((path.clone(), file.resolved_imports.take()?)))
+            .collect();
+    }
+
+    /// Replaces the cached files, which drops the files which no longer exist
+    pub fn set_files(&mut self, files: HashMap<PathBuf, CachedFile>) {
+        self.files = files;
+    }
+
+    pub fn set_resolved_imports(&mut self, resolved_imports: Vec<(PathBuf, ResolvedImports)>) {
+        for (path, resolved_imports) in resolved_imports {
+            if let Some(file) = self.files.get_mut(&path) {
+                file.resolved_imports = Some(resolved_imports);
+            }
+        }
+    }
+
+    /// Writes the cache, returning a description of the failure if it could not be written - eg because the cache
+    /// directory is read-only or the disk is full
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                format!(
+                    "Unable to create cache directory {}: {}",
+                    parent.display(),
+                    e
+                )
+            })?;
+        }
+        // written to a temporary file first so that a concurrent run never reads a partially written cache
+        let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        let result = File::create(&temp_path)
+            .map_err(serde_json::Error::io)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                serde_json::to_writer(&mut writer, self)?;
+                // flushed explicitly as errors when the writer is dropped are ignored - eg a full disk
+                return writer.flush().map_err(serde_json::Error::io);
+            })
+            .map_err(|e| format!("Unable to write cache {}: {}", temp_path.display(), e))
+            .and_then(|_| {
+                fs::rename(&temp_path, path)
+                    .map_err(|e| format!("Unable to write cache {}: {}", path.display(), e))
+            });
+        if result.is_err() {
+            // don't leave a partially written file behind
+            let _ = fs::remove_file(&temp_path);
+        }
+
+        return result;
+    }
+}
+
+/// Hashes the options which change what is extracted from a file
+pub fn get_parse_fingerprint(call_patterns: &[CallPattern]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // the extracted data can change between versions of athena
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    for call_pattern in call_patterns {
+        call_pattern.callee.hash(&mut hasher);
+        call_pattern.arg_index.hash(&mut hasher);
+    }
+    return hasher.finish();
+}
+
+/// Hashes the inputs which change how the imports of a file resolve.
+/// Adding or removing any file can change how an import resolves (eg `./foo` to `./foo.ts` or `./foo/index.ts`),
+/// so the whole set of files is included.
+pub fn get_resolution_fingerprint(
+    files: &[PathBuf],
+    tsconfig: &TSConfig,
+    package_jsons: &[PackageJson],
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+
+    let mut sorted_files = files.iter().collect::<Vec<_>>();
+    sorted_files.sort();
+    sorted_files.hash(&mut hasher);
+
+    tsconfig.base_url.hash(&mut hasher);
+    if let Some(paths) = &tsconfig.paths {
+        let mut sorted_paths = paths.iter().collect::<Vec<_>>();
+        sorted_paths.sort();
+        sorted_paths.hash(&mut hasher);
+    }
+
+    for package_json in package_jsons {
+        package_json.path.hash(&mut hasher);
+        package_json.name.hash(&mut hasher);
+        package_json.public_subpaths.hash(&mut hasher);
+    }
+
+    return hasher.finish();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        call_pattern::jest_call_patterns,
+        test_utils::{parse_test_file, TestProject},
+    };
+
+    fn get_cached_file(path: &Path) -> CachedFile {
+        return CachedFile {
+            fingerprint: FileFingerprint::new(path).unwrap(),
+            parsed_module: parse_test_file(path, &[]),
+            errors: vec![],
+            resolved_imports: Some(ResolvedImports {
+                imports: vec![],
+                errors: vec![],
+                unresolved_paths: vec![],
+                wildcard_globs: vec![],
+            }),
+        };
+    }
+
+    #[test]
+    fn files_are_reused_until_they_change() {
+        let project = TestProject::new(&[("a.ts", "import './b';"), ("b.ts", "export {};")]);
+        let cache_path = project.path("cache/graph.json");
+        let parse_fingerprint = get_parse_fingerprint(&[]);
+
+        let mut cache = GraphCache::new(parse_fingerprint, 0);
+        cache.set_files(HashMap::from([
+            (project.path("a.ts"), get_cached_file(&project.path("a.ts"))),
+            (project.path("b.ts"), get_cached_file(&project.path("b.ts"))),
+        ]));
+        cache.write(&cache_path).unwrap();
+        project.write("b.ts", "export const b = 1;");
+
+        let cache = GraphCache::read(&cache_path, parse_fingerprint);
+        let a_fingerprint = FileFingerprint::new(&project.path("a.ts")).unwrap();
+        let cached_a = cache.get_file(&project.path("a.ts"), &a_fingerprint);
+        assert_eq!(cached_a.unwrap().parsed_module.dependencies.len(), 1);
+        let b_fingerprint = FileFingerprint::new(&project.path("b.ts")).unwrap();
+        assert!(cache
+            .get_file(&project.path("b.ts"), &b_fingerprint)
+            .is_none());
+
+        // changing the call patterns changes what is extracted from every file
+        let cache = GraphCache::read(&cache_path, get_parse_fingerprint(&jest_call_patterns()));
+        assert!(cache
+            .get_file(&project.path("a.ts"), &a_fingerprint)
+            .is_none());
+    }
+
+    #[test]
+    fn resolved_imports_are_discarded_when_the_resolution_inputs_change() {
+        let project = TestProject::new(&[("a.ts", "import './b';")]);
+        let files = vec![project.path("a.ts")];
+        let resolution_fingerprint = get_resolution_fingerprint(&files, &TSConfig::default(), &[]);
+
+        let mut cache = GraphCache::new(0, resolution_fingerprint);
+        cache.set_files(HashMap::from([(
+            project.path("a.ts"),
+            get_cached_file(&project.path("a.ts")),
+        )]));
+        cache.set_resolution_fingerprint(resolution_fingerprint);
+        assert!(cache.files[&project.path("a.ts")]
+            .resolved_imports
+            .is_some());
+
+        // `./b` could now resolve to the new file
+        project.write("b.ts", "export {};");
+        let files = vec![project.path("a.ts"), project.path("b.ts")];
+        cache.set_resolution_fingerprint(get_resolution_fingerprint(
+            &files,
+            &TSConfig::default(),
+            &[],
+        ));
+        assert!(cache.take_resolved_imports().is_empty());
+    }
+
+    #[test]
+    fn write_failures_are_returned() {
+        let project = TestProject::new(&[("file", "")]);
+        let cache = GraphCache::new(0, 0);
+
+        // the cache directory can't be created inside of a file
+        let result = cache.write(&project.path("file/graph.json"));
+        assert!(result
+            .unwrap_err()
+            .starts_with("Unable to create cache directory"));
+    }
+}
@@ -0,0 +1,198 @@
+use petgraph::{graph::NodeIndex, Direction};
+use rayon::prelude::*;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use crate::{
+    cycles::{find_cycles, Cycle},
+    depth_first_expansion::DepthFirstExpansion,
+    module::{ModuleGraph, ModuleId, ModuleKind},
+    snapshot::GraphSnapshot,
+};
+
+pub struct DependencyGrowth {
+    pub path: PathBuf,
+    pub base_count: usize,
+    pub head_count: usize,
+}
+
+pub struct GraphDiff {
+    pub added_edges: Vec<(PathBuf, PathBuf)>,
+    pub removed_edges: Vec<(PathBuf, PathBuf)>,
+    /// Cycles in the head which are not contained within a cycle in the base
+    pub new_cycles: Vec<Cycle>,
+    pub new_packages: Vec<PathBuf>,
+    /// Modules whose transitive dependency count grew by more than the threshold, largest growth first
+    pub dependency_growth: Vec<DependencyGrowth>,
+}
+
+/// A snapshot alongside its graph, so the same lookups can be done for the base and the head
+struct SnapshotGraph<'a> {
+    snapshot: &'a GraphSnapshot,
+    graph: ModuleGraph,
+    path_to_index: HashMap<&'a str, usize>,
+}
+impl<'a> SnapshotGraph<'a> {
+    fn new(snapshot: &'a GraphSnapshot) -> Self {
+        return Self {
+            snapshot,
+            graph: snapshot.to_module_graph(),
+            path_to_index: snapshot
+                .modules
+                .iter()
+                .enumerate()
+                .map(|(index, module)| (module.path.as_str(), index))
+                .collect(),
+        };
+    }
+
+    fn get_path(&self, index: usize) -> &'a str {
+        return &self.snapshot.modules[index].path;
+    }
+
+    fn get_edges(&self) -> HashSet<(&'a str, &'a str)> {
+        return self
+            .snapshot
+            .edges
+            .iter()
+            .map(|edge| (self.get_path(edge.from), self.get_path(edge.to)))
+            .collect();
+    }
+
+    fn get_cycles(&self, ignore_type_only: bool) -> Vec<(Vec<&'a str>, Vec<&'a str>)> {
+        let to_paths = |module_ids: Vec<ModuleId>| -> Vec<&'a str> {
+            return module_ids
+                .into_iter()
+                .map(|module_id| self.get_path(module_id.into()))
+                .collect();
+        };
+        return find_cycles(&self.graph, ignore_type_only)
+            .into_iter()
+            .map(|(modules, shortest_loop)| (to_paths(modules), to_paths(shortest_loop)))
+            .collect();
+    }
+
+    /// The paths of every module which can reach one of the given modules
+    fn get_ancestors(&self, paths: impl Iterator<Item = &'a str>) -> HashSet<&'a str> {
+        let roots = paths
+            .filter_map(|path| self.path_to_index.get(path))
+            .map(|index| NodeIndex::new(*index))
+            .collect();
+        return DepthFirstExpansion::new(&self.graph, Direction::Incoming, roots)
+            .map(|node_idx| self.get_path(node_idx.index()))
+            .collect();
+    }
+
+    fn get_transitive_dependency_count(&self, path: &str) -> Option<usize> {
+        let index = self.path_to_index.get(path)?;
+        let reachable = DepthFirstExpansion::new(
+            &self.graph,
+            Direction::Outgoing,
+            vec![NodeIndex::new(*index)],
+        )
+        .count();
+        // the module itself is not a dependency
+        return Some(reachable - 1);
+    }
+}
+
+/// Compares two snapshots of the graph - eg the base branch and a PR
+pub fn diff_snapshots(
+    base: &GraphSnapshot,
+    head: &GraphSnapshot,
+    growth_threshold: usize,
+    ignore_type_only: bool,
+) -> GraphDiff {
+    let base = SnapshotGraph::new(base);
+    let head = SnapshotGraph::new(head);
+    let to_path_pairs = |edges: Vec<&(&str, &str)>| -> Vec<(PathBuf, PathBuf)> {
+        let mut edges = edges
+            .into_iter()
+            .map(|(from, to)| (PathBuf::from(from), PathBuf::from(to)))
+            .collect::<Vec<_>>();
+        edges.sort();
+        return edges;
+    };
+
+    let base_edges = base.get_edges();
+    let head_edges = head.get_edges();
+    let added_edges = head_edges.difference(&base_edges).collect::<Vec<_>>();
+    let removed_edges = base_edges.difference(&head_edges).collect::<Vec<_>>();
+
+    // a cycle that grew is reported, but a cycle that shrank or stayed the same is not
+    let base_cycles = base
+        .get_cycles(ignore_type_only)
+        .into_iter()
+        .map(|(modules, _)| modules.into_iter().collect::<HashSet<_>>())
+        .collect::<Vec<_>>();
+    let mut new_cycles = head
+        .get_cycles(ignore_type_only)
+        .into_iter()
+        .filter(|(modules, _)| {
+            return !base_cycles
+                .iter()
+                .any(|base_cycle| modules.iter().all(|path| base_cycle.contains(path)));
+        })
+        .map(|(modules, shortest_loop)| {
+            let mut modules = modules.into_iter().map(PathBuf::from).collect::<Vec<_>>();
+            modules.sort();
+            return Cycle {
+                modules,
+                shortest_loop: shortest_loop.into_iter().map(PathBuf::from).collect(),
+            };
+        })
+        .collect::<Vec<_>>();
+    new_cycles.sort_by_key(|cycle| std::cmp::Reverse(cycle.modules.len()));
+
+    let base_packages = base
+        .snapshot
+        .modules
+        .iter()
+        .filter(|module| module.kind == ModuleKind::NodeModule)
+        .map(|module| module.path.as_str())
+        .collect::<HashSet<_>>();
+    let mut new_packages = head
+        .snapshot
+        .modules
+        .iter()
+        .filter(|module| module.kind == ModuleKind::NodeModule)
+        .filter(|module| !base_packages.contains(module.path.as_str()))
+        .map(|module| PathBuf::from(&module.path))
+        .collect::<Vec<_>>();
+    new_packages.sort();
+
+    // only modules which can reach a changed edge can have a different transitive dependency count, which saves us
+    // from counting the dependencies of every module in the graph
+    let mut candidates = head.get_ancestors(added_edges.iter().map(|(from, _)| *from));
+    candidates.extend(base.get_ancestors(removed_edges.iter().map(|(from, _)| *from)));
+    let mut dependency_growth = candidates
+        .into_par_iter()
+        .filter_map(|path| {
+            let base_count = base.get_transitive_dependency_count(path)?;
+            let head_count = head.get_transitive_dependency_count(path)?;
+            if head_count <= base_count + growth_threshold {
+                return None;
+            }
+            return Some(DependencyGrowth {
+                path: PathBuf::from(path),
+                base_count,
+                head_count,
+            });
+        })
+        .collect::<Vec<_>>();
+    dependency_growth.sort_by(|a, b| {
+        (b.head_count - b.base_count)
+            .cmp(&(a.head_count - a.base_count))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    return GraphDiff {
+        added_edges: to_path_pairs(added_edges),
+        removed_edges: to_path_pairs(removed_edges),
+        new_cycles,
+        new_packages,
+        dependency_growth,
+    };
+}
@@ -0,0 +1,420 @@
+use petgraph::visit::EdgeRef;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    file_system::get_file_size,
+    module::{DependencyKind, ModuleId, ModuleKind},
+};
+
+#[derive(Copy, Clone, Debug)]
+pub enum ExportFormat {
+    /// Graphviz
+    Dot,
+    /// A Mermaid flowchart, which renders in markdown on most code hosts
+    Mermaid,
+    GraphMl,
+    /// Gephi's native format
+    Gexf,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportNode {
+    /// Files are relative to the root, node modules are the package name
+    pub label: String,
+    pub kind: ModuleKind,
+    /// The name of the package the file belongs to, or the package name for node modules
+    pub package: Option<String>,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportEdge {
+    /// Index into `nodes`
+    pub from: usize,
+    /// Index into `nodes`
+    pub to: usize,
+    pub kind: DependencyKind,
+    pub is_type_only: bool,
+    /// The number of imports between the two modules - which can be large in a collapsed graph
+    pub count: usize,
+}
+
+/// The graph, or part of it, prepared for writing in a visualization format.
+/// Parallel edges are merged in the same way as a snapshot, with the number of merged imports kept as the count.
+pub struct GraphExport {
+    pub nodes: Vec<ExportNode>,
+    pub edges: Vec<ExportEdge>,
+}
+
+impl GraphExport {
+    /// Prepares the subgraph of the given modules, or the whole graph if `None`, with file paths relative to `root`
+    pub fn new<'a>(
+        graph: &'a DependencyGraph,
+        module_ids: Option<&HashSet<ModuleId>>,
+        root: &Path,
+    ) -> Result<Self, &'a str> {
+        let module_graph = graph.get_graph()?;
+
+        let mut modules = graph
+            .get_module_ids()
+            .filter(|module_id| module_ids.is_none_or(|module_ids| module_ids.contains(module_id)))
+            .map(|module_id| (module_id, graph.get_path_for_module_id(module_id)))
+            .collect::<Vec<_>>();
+        modules.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+        let nodes = modules
+            .par_iter()
+            .map(|(module_id, path)| {
+                let kind = graph.get_module_kind(*module_id);
+                return match kind {
+                    ModuleKind::File => ExportNode {
+                        label: path
+                            .strip_prefix(root)
+                            .unwrap_or(path)
+                            .display()
+                            .to_string(),
+                        kind,
+                        package: graph
+                            .get_package_json_index_for_module_id(*module_id)
+                            .and_then(|index| graph.get_package_jsons()[index].name.clone()),
+                        size: get_file_size(path),
+                    },
+                    ModuleKind::NodeModule => ExportNode {
+                        label: path.display().to_string(),
+                        kind,
+                        package: Some(path.display().to_string()),
+                        size: 0,
+                    },
+                };
+            })
+            .collect::<Vec<_>>();
+
+        let module_id_to_index = modules
+            .iter()
+            .enumerate()
+            .map(|(index, (module_id, _))| (*module_id, index))
+            .collect::<HashMap<_, _>>();
+
+        let mut edges: HashMap<(usize, usize), ExportEdge> = HashMap::new();
+        for edge in module_graph.edge_references() {
+            let (Some(from), Some(to)) = (
+                module_id_to_index.get(&module_graph[edge.source()]),
+                module_id_to_index.get(&module_graph[edge.target()]),
+            ) else {
+                continue;
+            };
+            let weight = edge.weight();
+            edges
+                .entry((*from, *to))
+                .and_modify(|export_edge| {
+                    if weight.kind == DependencyKind::Static {
+                        export_edge.kind = DependencyKind::Static;
+                    }
+                    export_edge.is_type_only &= weight.is_type_only;
+                    export_edge.count += 1;
+                })
+                .or_insert(ExportEdge {
+                    from: *from,
+                    to: *to,
+                    kind: weight.kind,
+                    is_type_only: weight.is_type_only,
+                    count: 1,
+                });
+        }
+        let mut edges = edges.into_values().collect::<Vec<_>>();
+        edges.sort_by_key(|edge| (edge.from, edge.to));
+
+        return Ok(Self { nodes, edges });
+    }
+
+    pub fn write(&self, format: ExportFormat, writer: &mut impl Write) -> io::Result<()> {
+        return match format {
+            ExportFormat::Dot => self.write_dot(writer),
+            ExportFormat::Mermaid => self.write_mermaid(writer),
+            ExportFormat::GraphMl => self.write_graphml(writer),
+            ExportFormat::Gexf => self.write_gexf(writer),
+        };
+    }
+
+    fn write_dot(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "digraph athena {{")?;
+        writeln!(writer, "  node [shape=box];")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            write!(
+                writer,
+                "  n{} [label=\"{}\", kind=\"{}\", size={}",
+                index,
+                escape_dot(&node.label),
+                get_module_kind_name(node.kind),
+                node.size
+            )?;
+            if let Some(package) = &node.package {
+                write!(writer, ", package=\"{}\"", escape_dot(package))?;
+            }
+            if node.kind == ModuleKind::NodeModule {
+                write!(writer, ", shape=ellipse")?;
+            }
+            writeln!(writer, "];")?;
+        }
+        for edge in self.edges.iter() {
+            write!(
+                writer,
+                "  n{} -> n{} [kind=\"{}\", type_only={}, count={}",
+                edge.from,
+                edge.to,
+                get_dependency_kind_name(edge.kind),
+                edge.is_type_only,
+                edge.count
+            )?;
+            if edge.count > 1 {
+                write!(writer, ", label=\"{}\"", edge.count)?;
+            }
+            if edge.is_type_only {
+                write!(writer, ", style=dashed")?;
+            }
+            writeln!(writer, "];")?;
+        }
+        writeln!(writer, "}}")?;
+
+        return Ok(());
+    }
+
+    fn write_mermaid(&self, writer: &mut impl Write) -> io::Result<()> {
+        // mermaid has no arbitrary attributes, so the kinds are shown with the node shape and edge style instead
+        writeln!(writer, "flowchart LR")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            let label = escape_mermaid(&node.label);
+            match node.kind {
+                ModuleKind::File => writeln!(writer, "  n{}[\"{}\"]", index, label)?,
+                ModuleKind::NodeModule => writeln!(writer, "  n{}([\"{}\"])", index, label)?,
+            }
+        }
+        for edge in self.edges.iter() {
+            let arrow = if edge.is_type_only { "-.->" } else { "-->" };
+            if edge.count > 1 {
+                writeln!(
+                    writer,
+                    "  n{} {}|{}| n{}",
+                    edge.from, arrow, edge.count, edge.to
+                )?;
+            } else {
+                writeln!(writer, "  n{} {} n{}", edge.from, arrow, edge.to)?;
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn write_graphml(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            writer,
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">"
+        )?;
+        writeln!(
+            writer,
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>"
+        )?;
+        writeln!(
+            writer,
+            "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>"
+        )?;
+        writeln!(
+            writer,
+            "  <key id=\"package\" for=\"node\" attr.name=\"package\" attr.type=\"string\"/>"
+        )?;
+        writeln!(
+            writer,
+            "  <key id=\"size\" for=\"node\" attr.name=\"size\" attr.type=\"long\"/>"
+        )?;
+        writeln!(
+            writer,
+            "  <key id=\"importKind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>"
+        )?;
+        writeln!(
+            writer,
+            "  <key id=\"typeOnly\" for=\"edge\" attr.name=\"typeOnly\" attr.type=\"boolean\"/>"
+        )?;
+        writeln!(
+            writer,
+            "  <key id=\"count\" for=\"edge\" attr.name=\"count\" attr.type=\"int\"/>"
+        )?;
+        writeln!(writer, "  <graph id=\"athena\" edgedefault=\"directed\">")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            writeln!(writer, "    <node id=\"n{}\">", index)?;
+            writeln!(
+                writer,
+                "      <data key=\"label\">{}</data>",
+                escape_xml(&node.label)
+            )?;
+            writeln!(
+                writer,
+                "      <data key=\"kind\">{}</data>",
+                get_module_kind_name(node.kind)
+            )?;
+            if let Some(package) = &node.package {
+                writeln!(
+                    writer,
+                    "      <data key=\"package\">{}</data>",
+                    escape_xml(package)
+                )?;
+            }
+            writeln!(writer, "      <data key=\"size\">{}</data>", node.size)?;
+            writeln!(writer, "    </node>")?;
+        }
+        for edge in self.edges.iter() {
+            writeln!(
+                writer,
+                "    <edge source=\"n{}\" target=\"n{}\">",
+                edge.from, edge.to
+            )?;
+            writeln!(
+                writer,
+                "      <data key=\"importKind\">{}</data>",
+                get_dependency_kind_name(edge.kind)
+            )?;
+            writeln!(
+                writer,
+                "      <data key=\"typeOnly\">{}</data>",
+                edge.is_type_only
+            )?;
+            writeln!(writer, "      <data key=\"count\">{}</data>", edge.count)?;
+            writeln!(writer, "    </edge>")?;
+        }
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</graphml>")?;
+
+        return Ok(());
+    }
+
+    fn write_gexf(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            writer,
+            "<gexf xmlns=\"http://gexf.net/1.3\" version=\"1.3\">"
+        )?;
+        writeln!(writer, "  <graph defaultedgetype=\"directed\">")?;
+        writeln!(writer, "    <attributes class=\"node\">")?;
+        writeln!(
+            writer,
+            "      <attribute id=\"kind\" title=\"kind\" type=\"string\"/>"
+        )?;
+        writeln!(
+            writer,
+            "      <attribute id=\"package\" title=\"package\" type=\"string\"/>"
+        )?;
+        writeln!(
+            writer,
+            "      <attribute id=\"size\" title=\"size\" type=\"long\"/>"
+        )?;
+        writeln!(writer, "    </attributes>")?;
+        writeln!(writer, "    <attributes class=\"edge\">")?;
+        writeln!(
+            writer,
+            "      <attribute id=\"kind\" title=\"kind\" type=\"string\"/>"
+        )?;
+        writeln!(
+            writer,
+            "      <attribute id=\"typeOnly\" title=\"typeOnly\" type=\"boolean\"/>"
+        )?;
+        writeln!(writer, "    </attributes>")?;
+        writeln!(writer, "    <nodes>")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            writeln!(
+                writer,
+                "      <node id=\"n{}\" label=\"{}\">",
+                index,
+                escape_xml(&node.label)
+            )?;
+            writeln!(writer, "        <attvalues>")?;
+            writeln!(
+                writer,
+                "          <attvalue for=\"kind\" value=\"{}\"/>",
+                get_module_kind_name(node.kind)
+            )?;
+            if let Some(package) = &node.package {
+                writeln!(
+                    writer,
+                    "          <attvalue for=\"package\" value=\"{}\"/>",
+                    escape_xml(package)
+                )?;
+            }
+            writeln!(
+                writer,
+                "          <attvalue for=\"size\" value=\"{}\"/>",
+                node.size
+            )?;
+            writeln!(writer, "        </attvalues>")?;
+            writeln!(writer, "      </node>")?;
+        }
+        writeln!(writer, "    </nodes>")?;
+        writeln!(writer, "    <edges>")?;
+        for (index, edge) in self.edges.iter().enumerate() {
+            // gephi uses the weight to size the edge, so the import count is a natural fit
+            writeln!(
+                writer,
+                "      <edge id=\"e{}\" source=\"n{}\" target=\"n{}\" weight=\"{}\">",
+                index, edge.from, edge.to, edge.count
+            )?;
+            writeln!(writer, "        <attvalues>")?;
+            writeln!(
+                writer,
+                "          <attvalue for=\"kind\" value=\"{}\"/>",
+                get_dependency_kind_name(edge.kind)
+            )?;
+            writeln!(
+                writer,
+                "          <attvalue for=\"typeOnly\" value=\"{}\"/>",
+                edge.is_type_only
+            )?;
+            writeln!(writer, "        </attvalues>")?;
+            writeln!(writer, "      </edge>")?;
+        }
+        writeln!(writer, "    </edges>")?;
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</gexf>")?;
+
+        return Ok(());
+    }
+}
+
+// the same names the kinds are serialized with in snapshots
+fn get_module_kind_name(kind: ModuleKind) -> &'static str {
+    return match kind {
+        ModuleKind::File => "file",
+        ModuleKind::NodeModule => "node-module",
+    };
+}
+
+fn get_dependency_kind_name(kind: DependencyKind) -> &'static str {
+    return match kind {
+        DependencyKind::Static => "static",
+        DependencyKind::WildcardDynamic => "wildcard-dynamic",
+    };
+}
+
+fn escape_dot(value: &str) -> String {
+    return value.replace('\\', "\\\\").replace('"', "\\\"");
+}
+
+fn escape_mermaid(value: &str) -> String {
+    return value.replace('"', "#quot;");
+}
+
+fn escape_xml(value: &str) -> String {
+    return value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;");
+}
@@ -0,0 +1,61 @@
+use serde::Serialize;
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    cycles::get_cycles,
+    dependency_graph::DependencyGraph,
+    graph_export::{ExportEdge, ExportNode, GraphExport},
+};
+
+// the viewer is a single static page, with the graph data substituted in for the placeholder
+const TEMPLATE: &str = include_str!("html_report.html");
+const DATA_PLACEHOLDER: &str = "/*ATHENA_DATA*/ null";
+
+#[derive(Serialize)]
+struct ReportData<'a> {
+    nodes: &'a [ExportNode],
+    edges: &'a [ExportEdge],
+    /// The indexes into `nodes` of the modules in each cycle, largest first
+    cycles: Vec<Vec<usize>>,
+}
+
+/// Builds a self-contained HTML page for exploring the graph, with file paths relative to `root`
+pub fn get_html_report<'a>(graph: &'a DependencyGraph, root: &Path) -> Result<String, &'a str> {
+    let export = GraphExport::new(graph, None, root)?;
+    let label_to_index = export
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (node.label.as_str(), index))
+        .collect::<HashMap<_, _>>();
+
+    let cycles = get_cycles(graph, false)?
+        .iter()
+        .map(|cycle| {
+            return cycle
+                .modules
+                .iter()
+                .map(|path| {
+                    let label = path
+                        .strip_prefix(root)
+                        .unwrap_or(path)
+                        .display()
+                        .to_string();
+                    return label_to_index[label.as_str()];
+                })
+                .collect::<Vec<_>>();
+        })
+        .collect::<Vec<_>>();
+
+    let data = serde_json::to_string(&ReportData {
+        nodes: &export.nodes,
+        edges: &export.edges,
+        cycles,
+    })
+    .expect("Unable to serialize the report data");
+
+    // a path containing `</script>` would otherwise end the script element early
+    let data = data.replace("</", "<\\/");
+
+    return Ok(TEMPLATE.replacen(DATA_PLACEHOLDER, &data, 1));
+}
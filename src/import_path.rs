@@ -0,0 +1,282 @@
+use petgraph::{algo::all_simple_paths, graph::NodeIndex, Direction};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    dependency_graph::DependencyGraph, import_visitor::SourceLocation, module::ModuleGraph,
+};
+
+#[derive(Clone, Copy)]
+pub enum PathQuery {
+    /// The `k` shortest paths
+    Shortest(usize),
+    /// Every simple path with at most this many imports
+    AllSimple(usize),
+}
+
+pub struct ImportHop {
+    pub from: PathBuf,
+    /// The import string as it was written in the source file
+    pub specifier: PathBuf,
+    pub location: SourceLocation,
+}
+
+pub struct ImportPath {
+    pub hops: Vec<ImportHop>,
+    pub to: PathBuf,
+}
+
+/// Finds the import chains which cause `from` to depend on `to`, shortest first
+pub fn get_import_paths<'a>(
+    graph: &'a DependencyGraph,
+    from: &Path,
+    to: &Path,
+    query: PathQuery,
+) -> Result<Vec<ImportPath>, &'a str> {
+    let module_graph = graph.get_graph()?;
+    let from_node = graph.get_node_for_module_id(
+        graph
+            .get_module_id_for_path(from)
+            .ok_or("Unable to get module for the from path")?,
+    );
+    let to_node = graph.get_node_for_module_id(
+        graph
+            .get_module_id_for_path(to)
+            .ok_or("Unable to get module for the to path")?,
+    );
+
+    let node_paths: Vec<Vec<NodeIndex>> = match query {
+        PathQuery::Shortest(k) => find_k_shortest_paths(module_graph, from_node, to_node, k),
+        PathQuery::AllSimple(max_length) => {
+            let mut paths = all_simple_paths::<Vec<NodeIndex>, _>(
+                module_graph,
+                from_node,
+                to_node,
+                0,
+                Some(max_length.saturating_sub(1)),
+            )
+            .collect::<Vec<_>>();
+            // parallel edges (the same module imported twice) are each followed separately, yielding the same path
+            // of modules more than once
+            let mut seen_paths = HashSet::new();
+            paths.retain(|path| seen_paths.insert(path.clone()));
+            paths.sort_by_key(|path| path.len());
+            paths
+        }
+    };
+
+    return Ok(node_paths
+        .iter()
+        .map(|node_path| ImportPath {
+            hops: node_path
+                .windows(2)
+                .map(|pair| get_hop(graph, module_graph, pair[0], pair[1]))
+                .collect(),
+            to: graph.get_path_for_module_id(module_graph[to_node]),
+        })
+        .collect());
+}
+
+/// Describes the import between two adjacent modules in a path.
+/// If a module imports the same dependency multiple times, the first import is used.
+fn get_hop(
+    graph: &DependencyGraph,
+    module_graph: &ModuleGraph,
+    from_node: NodeIndex,
+    to_node: NodeIndex,
+) -> ImportHop {
+    let from_module_id = module_graph[from_node];
+    let dependency = module_graph
+        .edges_connecting(from_node, to_node)
+        .filter_map(|edge| graph.get_dependency_for_edge(from_module_id, edge.weight()))
+        .min_by_key(|dependency| (dependency.location.line, dependency.location.column))
+        .expect("Adjacent modules in a path should be connected by an edge");
+
+    return ImportHop {
+        from: graph.get_path_for_module_id(from_module_id),
+        specifier: dependency.specifier.clone(),
+        location: dependency.location,
+    };
+}
+
+/// Yen's algorithm - each subsequent path is found by "spurring" off of the previous path with its used edges removed
+fn find_k_shortest_paths(
+    graph: &ModuleGraph,
+    from: NodeIndex,
+    to: NodeIndex,
+    k: usize,
+) -> Vec<Vec<NodeIndex>> {
+    let mut shortest_paths: Vec<Vec<NodeIndex>> = vec![];
+    match find_shortest_path(graph, from, to, &HashSet::new(), &HashSet::new()) {
+        Some(path) => shortest_paths.push(path),
+        None => return shortest_paths,
+    }
+
+    let mut candidates: Vec<Vec<NodeIndex>> = vec![];
+    while shortest_paths.len() < k {
+        let previous_path = shortest_paths.last().unwrap().clone();
+        for spur_idx in 0..previous_path.len() - 1 {
+            let spur_node = previous_path[spur_idx];
+            let root_path = &previous_path[..=spur_idx];
+
+            // remove the edges already taken from this root so we find a new path
+            let blocked_edges = shortest_paths
+                .iter()
+                .filter(|path| path.len() > spur_idx + 1 && &path[..=spur_idx] == root_path)
+                .map(|path| (path[spur_idx], path[spur_idx + 1]))
+                .collect::<HashSet<_>>();
+            // the path must stay simple, so it can't revisit the root
+            let blocked_nodes = root_path[..spur_idx]
+                .iter()
+                .copied()
+                .collect::<HashSet<_>>();
+
+            if let Some(spur_path) =
+                find_shortest_path(graph, spur_node, to, &blocked_nodes, &blocked_edges)
+            {
+                let mut candidate = root_path[..spur_idx].to_vec();
+                candidate.extend(spur_path);
+                if !candidates.contains(&candidate) && !shortest_paths.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            break;
+        }
+        // stable sort so that ties are resolved in the order they were found
+        candidates.sort_by_key(|path| path.len());
+        shortest_paths.push(candidates.remove(0));
+    }
+
+    return shortest_paths;
+}
+
+fn find_shortest_path(
+    graph: &ModuleGraph,
+    from: NodeIndex,
+    to: NodeIndex,
+    blocked_nodes: &HashSet<NodeIndex>,
+    blocked_edges: &HashSet<(NodeIndex, NodeIndex)>,
+) -> Option<Vec<NodeIndex>> {
+    let mut parents: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    let mut seen = HashSet::from([from]);
+
+    while let Some(node_idx) = queue.pop_front() {
+        if node_idx == to {
+            let mut path = vec![to];
+            let mut current = to;
+            while current != from {
+                current = parents[&current];
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for neighbor in graph.neighbors_directed(node_idx, Direction::Outgoing) {
+            if blocked_nodes.contains(&neighbor)
+                || blocked_edges.contains(&(node_idx, neighbor))
+                || !seen.insert(neighbor)
+            {
+                continue;
+            }
+            parents.insert(neighbor, node_idx);
+            queue.push_back(neighbor);
+        }
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestProject;
+
+    fn get_path_modules(project: &TestProject, query: PathQuery) -> Vec<Vec<PathBuf>> {
+        let graph = project.build_graph();
+        return get_import_paths(
+            &graph,
+            &project.path("index.ts"),
+            &project.path("c.ts"),
+            query,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|path| {
+            let mut modules = path
+                .hops
+                .into_iter()
+                .map(|hop| hop.from)
+                .collect::<Vec<_>>();
+            modules.push(path.to);
+            return modules;
+        })
+        .collect();
+    }
+
+    #[test]
+    fn shortest_paths_are_listed_shortest_first() {
+        let project = TestProject::new(&[
+            ("index.ts", "import './a';\nimport './b';"),
+            ("a.ts", "import './c';"),
+            ("b.ts", "import './d';"),
+            ("d.ts", "import './c';"),
+            ("c.ts", "export {};"),
+        ]);
+
+        assert_eq!(
+            get_path_modules(&project, PathQuery::Shortest(1)),
+            vec![vec![
+                project.path("index.ts"),
+                project.path("a.ts"),
+                project.path("c.ts")
+            ]]
+        );
+        assert_eq!(
+            get_path_modules(&project, PathQuery::Shortest(5)),
+            vec![
+                vec![
+                    project.path("index.ts"),
+                    project.path("a.ts"),
+                    project.path("c.ts")
+                ],
+                vec![
+                    project.path("index.ts"),
+                    project.path("b.ts"),
+                    project.path("d.ts"),
+                    project.path("c.ts")
+                ],
+            ]
+        );
+        assert_eq!(get_path_modules(&project, PathQuery::AllSimple(2)).len(), 1);
+    }
+
+    #[test]
+    fn modules_imported_twice_give_one_path_from_the_first_import() {
+        let project = TestProject::new(&[
+            ("index.ts", "import './a';\nimport { a } from './a';"),
+            ("a.ts", "import './c';\nexport const a = 1;"),
+            ("c.ts", "export {};"),
+        ]);
+        let graph = project.build_graph();
+
+        for query in [PathQuery::Shortest(5), PathQuery::AllSimple(5)] {
+            let paths = get_import_paths(
+                &graph,
+                &project.path("index.ts"),
+                &project.path("c.ts"),
+                query,
+            )
+            .unwrap();
+            assert_eq!(paths.len(), 1);
+            assert_eq!(paths[0].hops[0].specifier, PathBuf::from("./a"));
+            assert_eq!(paths[0].hops[0].location.line, 1);
+        }
+    }
+}
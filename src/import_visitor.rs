@@ -1,46 +1,277 @@
+use serde::{Deserialize, Serialize};
 use std::{path::PathBuf, str::FromStr};
 
 use swc_atoms::{js_word, JsWord};
+use swc_common::{sync::Lrc, SourceMap, Span, Spanned};
 use swc_ecma_ast::{
-    CallExpr, ExportAll, Expr, ImportDecl, Lit, NamedExport, TsImportType, TsModuleRef,
+    AssignExpr, AssignOp, BinaryOp, CallExpr, Decl, ExportAll, ExportDecl, ExportDefaultDecl,
+    ExportDefaultExpr, ExportSpecifier, Expr, ImportDecl, ImportSpecifier, Lit, MemberProp,
+    MetaPropKind, ModuleExportName, NamedExport, ObjectPatProp, Pat, PatOrExpr, Prop, PropName,
+    PropOrSpread, TsEntityName, TsExportAssignment, TsImportEqualsDecl, TsImportType, TsModuleName,
+    TsModuleRef,
 };
-use swc_ecma_visit::VisitMut;
+use swc_ecma_visit::{VisitMut, VisitMutWith};
 
-pub struct ImportVisitor {
-    pub errors: Vec<String>,
-    pub dependencies: Vec<PathBuf>,
+use crate::{
+    call_pattern::CallPattern,
+    diagnostic::{Diagnostic, DiagnosticRule},
+    module::DependencyKind,
+};
+
+/// The name a default export is imported and re-exported by
+pub const DEFAULT_EXPORT_NAME: &str = "default";
+
+/// A 1-based line and column within a file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLocation {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// A single binding pulled in by an import
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ImportBinding {
+    /// import foo from 'mod';
+    Default { local: String },
+    /// import { foo as bar } from 'mod';
+    Named { imported: String, local: String },
+    /// import * as foo from 'mod';
+    /// Expressions like `require('mod')` and `import('mod')` also pull in the entire module, but have no local name
+    Namespace { local: Option<String> },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Dependency {
+    /// The raw import string - or a glob pattern if the import was a `DependencyKind::WildcardDynamic`
+    pub specifier: PathBuf,
+    pub kind: DependencyKind,
+    /// The bindings imported from the dependency.
+    /// This is empty for side-effect imports (`import 'mod'`) and re-exports (which are instead tracked as an `ExportBinding`)
+    pub imports: Vec<ImportBinding>,
+    /// Whether the import is erased at runtime - eg `import type { Foo } from 'mod'` or `type T = import('mod')`
+    pub is_type_only: bool,
+    pub location: SourceLocation,
+}
+
+/// A single name exported by a module
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ExportBinding {
+    /// export const foo = 1;
+    /// export { foo as bar };
+    /// export default foo;
+    Local {
+        name: String,
+        location: SourceLocation,
+    },
+    /// export { foo as bar } from 'mod';
+    /// export * as bar from 'mod'; -- `imported` is `None` as the entire module is re-exported
+    ReExport {
+        name: String,
+        imported: Option<String>,
+        /// The index of the re-exported module in the `dependencies` list
+        dependency_index: usize,
+        location: SourceLocation,
+    },
+    /// export * from 'mod';
+    ReExportAll {
+        /// The index of the re-exported module in the `dependencies` list
+        dependency_index: usize,
+        location: SourceLocation,
+    },
+}
+
+/// Everything we extract from a single file
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ParsedModule {
+    pub dependencies: Vec<Dependency>,
+    pub exports: Vec<ExportBinding>,
+}
+
+/// A piece of a partially static import string, eg `./locales/${lang}.json` is made up of
+/// `[Static("./locales/"), Wildcard, Static(".json")]`
+#[derive(Debug, PartialEq)]
+enum PatternPart {
+    Static(String),
+    Wildcard,
+}
+
+pub struct ImportVisitor<'a> {
+    pub errors: Vec<Diagnostic>,
+    pub dependencies: Vec<Dependency>,
+    pub exports: Vec<ExportBinding>,
+    call_patterns: &'a [CallPattern],
+    source_map: Lrc<SourceMap>,
 }
-impl ImportVisitor {
-    pub fn new() -> ImportVisitor {
+impl<'a> ImportVisitor<'a> {
+    pub fn new(source_map: Lrc<SourceMap>, call_patterns: &'a [CallPattern]) -> ImportVisitor<'a> {
         return ImportVisitor {
             errors: vec![],
             dependencies: vec![],
+            exports: vec![],
+            call_patterns,
+            source_map,
         };
     }
 
     // TODO(bradzacher) - handle /// <ref>s?
     // TODO(bradzacher) - catalogue `declare module 'mod'` as they create ambient node module declarations that are implicitly referenced
 
-    fn add_dependency(&mut self, dependency: &JsWord) {
-        self.dependencies
-            .push(PathBuf::from_str(dependency).expect("Expected a valid path"));
+    fn get_location(&self, span: Span) -> SourceLocation {
+        let loc = self.source_map.lookup_char_pos(span.lo);
+        return SourceLocation {
+            line: loc.line as u32,
+            column: loc.col.0 as u32 + 1,
+        };
     }
 
-    fn get_dependency_for_call_like_expr(&mut self, kind: &str, expr: &mut CallExpr) {
-        if expr.args.len() != 1 {
-            self.errors.push(format!(
-                "Expected a `{}` with exactly 1 string argument, found {} arguments",
+    fn push_error(&mut self, rule: DiagnosticRule, span: Span, message: String) {
+        let location = self.get_location(span);
+        self.errors.push(Diagnostic {
+            rule,
+            message,
+            location,
+        });
+    }
+
+    /// Adds a dependency and returns its index in the dependency list
+    fn push_dependency(
+        &mut self,
+        specifier: &str,
+        kind: DependencyKind,
+        imports: Vec<ImportBinding>,
+        is_type_only: bool,
+        span: Span,
+    ) -> usize {
+        let location = self.get_location(span);
+        self.dependencies.push(Dependency {
+            specifier: PathBuf::from_str(specifier).expect("Expected a valid path"),
+            kind,
+            imports,
+            is_type_only,
+            location,
+        });
+        return self.dependencies.len() - 1;
+    }
+
+    fn add_dependency(
+        &mut self,
+        dependency: &JsWord,
+        imports: Vec<ImportBinding>,
+        is_type_only: bool,
+        span: Span,
+    ) -> usize {
+        return self.push_dependency(
+            dependency,
+            DependencyKind::Static,
+            imports,
+            is_type_only,
+            span,
+        );
+    }
+
+    fn add_local_export(&mut self, name: &str, span: Span) {
+        let location = self.get_location(span);
+        self.exports.push(ExportBinding::Local {
+            name: name.to_string(),
+            location,
+        });
+    }
+
+    /// Adds a dependency for a template literal or string concatenation.
+    /// Much like webpack does, the dynamic parts are converted to a wildcard so that we can later match the pattern
+    /// against the discovered files - eg `./locales/${lang}.json` becomes `./locales/*.json`
+    fn add_pattern_dependency(
+        &mut self,
+        kind: &str,
+        expr: &Expr,
+        imports: Vec<ImportBinding>,
+        span: Span,
+    ) {
+        let mut parts: Vec<PatternPart> = vec![];
+        get_pattern_parts_for_expr(expr, &mut parts);
+
+        if !parts
+            .iter()
+            .any(|part| matches!(part, PatternPart::Wildcard))
+        {
+            // the expression was fully static - eg `./foo` or 'foo' + 'bar'
+            let specifier = parts
+                .iter()
+                .map(|part| match part {
+                    PatternPart::Static(value) => value.as_str(),
+                    PatternPart::Wildcard => unreachable!(),
+                })
+                .collect::<String>();
+            self.push_dependency(
+                &specifier,
+                DependencyKind::Static,
+                vec![ImportBinding::Namespace { local: None }],
+                false,
+                span,
+            );
+            return;
+        }
+
+        if !parts
+            .iter()
+            .any(|part| matches!(part, PatternPart::Static(_)))
+        {
+            self.push_error(DiagnosticRule::DynamicImport, span, format!(
+                "Found a dynamic `{}` with no static prefix or suffix, unable to resolve dependency",
                 kind,
-                expr.args.len(),
             ));
+            return;
+        }
+
+        let pattern = parts
+            .iter()
+            .map(|part| match part {
+                PatternPart::Static(value) => escape_glob(value),
+                PatternPart::Wildcard => "*".to_string(),
+            })
+            .collect::<String>();
+        self.push_dependency(
+            &pattern,
+            DependencyKind::WildcardDynamic,
+            imports,
+            false,
+            span,
+        );
+    }
+
+    /// `imports` are the bindings the call gives the caller - eg all of them for `require('mod')`, but none for
+    /// `jest.mock('mod')` which only references the module
+    fn get_dependency_for_call_like_expr(
+        &mut self,
+        kind: &str,
+        expr: &mut CallExpr,
+        arg_index: usize,
+        imports: Vec<ImportBinding>,
+    ) {
+        if expr.args.len() <= arg_index {
+            self.push_error(
+                DiagnosticRule::InvalidImportArgument,
+                expr.span,
+                format!(
+                    "Expected a `{}` with a string argument at index {}, found {} arguments",
+                    kind,
+                    arg_index,
+                    expr.args.len(),
+                ),
+            );
         } else {
-            match &*expr.args[0].expr {
+            match &*expr.args[arg_index].expr {
                 Expr::Lit(literal) => match literal {
-                    Lit::Str(str) => self.add_dependency(&str.value),
+                    Lit::Str(str) => {
+                        self.add_dependency(&str.value, imports, false, expr.span);
+                    }
                     default => {
-                        self.errors.push(format!(
-                            "Expected a `{}` with exactly 1 string argument, found 1 {:?} literal arguments",
+                        self.push_error(DiagnosticRule::InvalidImportArgument, expr.span, format!(
+                            "Expected a `{}` with a string argument at index {}, found a {:?} literal argument",
                             kind,
+                            arg_index,
                             // there's sadly no way to get the name of an enum in rust.
                             // the debug print will also print struct contents (which makes the log output ugly)
                             match default {
@@ -56,15 +287,33 @@ impl ImportVisitor {
                     }
                 },
                 Expr::Ident(_) => {
-                    self.errors.push(format!(
-                        "Found a dynamic `{}`, unable to resolve dependency",
+                    self.push_error(
+                        DiagnosticRule::DynamicImport,
+                        expr.span,
+                        format!("Found a dynamic `{}`, unable to resolve dependency", kind,),
+                    );
+                }
+                Expr::Tpl(_)
+                | Expr::Bin(swc_ecma_ast::BinExpr {
+                    op: BinaryOp::Add, ..
+                }) => {
+                    // import(`./locales/${lang}.json`)
+                    // require('./plugins/' + name)
+                    self.add_pattern_dependency(
                         kind,
-                    ));
+                        &expr.args[arg_index].expr,
+                        imports,
+                        expr.span,
+                    );
                 }
                 default => {
-                    self.errors.push(format!(
-                        "Expected a `{}` with exactly 1 string argument, found 1 {:?} arguments",
+                    self.push_error(
+                        DiagnosticRule::InvalidImportArgument,
+                        expr.span,
+                        format!(
+                        "Expected a `{}` with a string argument at index {}, found a {:?} argument",
                         kind,
+                        arg_index,
                         // there's sadly no way to get the name of an enum in rust.
                         // the debug print will also print struct contents (which makes the log output ugly)
                         match default {
@@ -82,7 +331,6 @@ impl ImportVisitor {
                             Expr::Call(_) => "Call Expression",
                             Expr::New(_) => "New Expression",
                             Expr::Seq(_) => "Sequence Expression",
-                            Expr::Tpl(_) => "Template Literal",
                             Expr::TaggedTpl(_) => "Tagged Template Literal",
                             Expr::Arrow(_) => "Arrow Function Expression",
                             Expr::Class(_) => "Class Expression",
@@ -102,68 +350,299 @@ impl ImportVisitor {
                             Expr::OptChain(_) => "Optional Chain Expression",
                             _ => "Unknown",
                         }
-                    ));
+                    ),
+                    );
                 }
             }
         }
     }
 }
-impl VisitMut for ImportVisitor {
+impl<'a> VisitMut for ImportVisitor<'a> {
     // type T = import('a');
+    // type T = import('a').Foo;
     fn visit_mut_ts_import_type(&mut self, expr: &mut TsImportType) {
-        self.add_dependency(&expr.arg.value);
+        let imports = match &expr.qualifier {
+            Some(qualifier) => {
+                let name = get_left_most_entity_name(qualifier);
+                vec![ImportBinding::Named {
+                    imported: name.to_string(),
+                    local: name.to_string(),
+                }]
+            }
+            None => vec![ImportBinding::Namespace { local: None }],
+        };
+        self.add_dependency(&expr.arg.value, imports, true, expr.span);
+        expr.visit_mut_children_with(self);
     }
 
     // import foo from 'bar';
     fn visit_mut_import_decl(&mut self, expr: &mut ImportDecl) {
-        self.add_dependency(&expr.src.value);
+        let imports = expr
+            .specifiers
+            .iter()
+            .map(|specifier| match specifier {
+                ImportSpecifier::Default(default) => ImportBinding::Default {
+                    local: default.local.sym.to_string(),
+                },
+                ImportSpecifier::Named(named) => ImportBinding::Named {
+                    imported: match &named.imported {
+                        Some(imported) => get_module_export_name(imported),
+                        None => named.local.sym.to_string(),
+                    },
+                    local: named.local.sym.to_string(),
+                },
+                ImportSpecifier::Namespace(namespace) => ImportBinding::Namespace {
+                    local: Some(namespace.local.sym.to_string()),
+                },
+            })
+            .collect();
+        // `import { type Foo } from 'mod'` is erased entirely when every specifier is type-only
+        let is_type_only = expr.type_only
+            || (!expr.specifiers.is_empty()
+                && expr.specifiers.iter().all(|specifier| match specifier {
+                    ImportSpecifier::Named(named) => named.is_type_only,
+                    ImportSpecifier::Default(_) | ImportSpecifier::Namespace(_) => false,
+                }));
+        self.add_dependency(&expr.src.value, imports, is_type_only, expr.span);
     }
 
     // import foo = ...;
-    fn visit_mut_ts_module_ref(&mut self, expr: &mut TsModuleRef) {
-        match expr {
+    // export import foo = ...;
+    fn visit_mut_ts_import_equals_decl(&mut self, expr: &mut TsImportEqualsDecl) {
+        match &expr.module_ref {
             // import foo = foo.bar; -- we ignore this case
             TsModuleRef::TsEntityName(_) => {}
             // import foo = require('bar');
             //              ^^^^^^^^^^^^^^
             TsModuleRef::TsExternalModuleRef(module_ref) => {
-                self.add_dependency(&module_ref.expr.value)
+                self.add_dependency(
+                    &module_ref.expr.value,
+                    vec![ImportBinding::Namespace {
+                        local: Some(expr.id.sym.to_string()),
+                    }],
+                    expr.is_type_only,
+                    expr.span,
+                );
             }
         }
+
+        if expr.is_export {
+            self.add_local_export(&expr.id.sym, expr.id.span);
+        }
     }
 
     // export * from 'bar';
     fn visit_mut_export_all(&mut self, expr: &mut ExportAll) {
-        self.add_dependency(&expr.src.value);
+        let dependency_index =
+            self.add_dependency(&expr.src.value, vec![], expr.type_only, expr.span);
+        let location = self.get_location(expr.span);
+        self.exports.push(ExportBinding::ReExportAll {
+            dependency_index,
+            location,
+        });
     }
 
     // export { foo } from 'bar';
+    // export { foo };
     fn visit_mut_named_export(&mut self, expr: &mut NamedExport) {
         match &expr.src {
-            Some(src) => self.add_dependency(&src.value),
+            Some(src) => {
+                let is_type_only = expr.type_only
+                    || (!expr.specifiers.is_empty()
+                        && expr.specifiers.iter().all(|specifier| match specifier {
+                            ExportSpecifier::Named(named) => named.is_type_only,
+                            ExportSpecifier::Namespace(_) | ExportSpecifier::Default(_) => false,
+                        }));
+                let dependency_index =
+                    self.add_dependency(&src.value, vec![], is_type_only, expr.span);
+                for specifier in expr.specifiers.iter() {
+                    let (name, imported) = match specifier {
+                        // export * as foo from 'bar';
+                        ExportSpecifier::Namespace(namespace) => {
+                            (get_module_export_name(&namespace.name), None)
+                        }
+                        // export foo from 'bar'; -- stage 1 proposal
+                        ExportSpecifier::Default(default) => (
+                            default.exported.sym.to_string(),
+                            Some(DEFAULT_EXPORT_NAME.to_string()),
+                        ),
+                        // export { foo as bar } from 'bar';
+                        ExportSpecifier::Named(named) => {
+                            let imported = get_module_export_name(&named.orig);
+                            let name = match &named.exported {
+                                Some(exported) => get_module_export_name(exported),
+                                None => imported.clone(),
+                            };
+                            (name, Some(imported))
+                        }
+                    };
+                    let location = self.get_location(specifier.span());
+                    self.exports.push(ExportBinding::ReExport {
+                        name,
+                        imported,
+                        dependency_index,
+                        location,
+                    });
+                }
+            }
             None => {
-                // export { foo }; -- we ignore this case
+                // export { foo as bar };
+                for specifier in expr.specifiers.iter() {
+                    if let ExportSpecifier::Named(named) = specifier {
+                        let name = match &named.exported {
+                            Some(exported) => get_module_export_name(exported),
+                            None => get_module_export_name(&named.orig),
+                        };
+                        self.add_local_export(&name, named.span);
+                    }
+                }
+            }
+        }
+    }
+
+    // export const foo = 1;
+    // export function foo() {}
+    fn visit_mut_export_decl(&mut self, expr: &mut ExportDecl) {
+        let mut names = vec![];
+        match &expr.decl {
+            Decl::Class(class) => names.push((class.ident.sym.to_string(), class.ident.span)),
+            Decl::Fn(function) => names.push((function.ident.sym.to_string(), function.ident.span)),
+            Decl::Var(var) => {
+                for declarator in var.decls.iter() {
+                    get_pattern_binding_names(&declarator.name, &mut names);
+                }
+            }
+            Decl::Using(_) => {
+                // using declarations cannot be exported
+            }
+            Decl::TsInterface(interface) => {
+                names.push((interface.id.sym.to_string(), interface.id.span))
+            }
+            Decl::TsTypeAlias(alias) => names.push((alias.id.sym.to_string(), alias.id.span)),
+            Decl::TsEnum(ts_enum) => names.push((ts_enum.id.sym.to_string(), ts_enum.id.span)),
+            Decl::TsModule(module) => match &module.id {
+                TsModuleName::Ident(ident) => names.push((ident.sym.to_string(), ident.span)),
+                // export declare module 'foo' {} -- this is an ambient module declaration, not an export
+                TsModuleName::Str(_) => {}
+            },
+        }
+        for (name, span) in names {
+            self.add_local_export(&name, span);
+        }
+
+        // the declaration can contain imports - eg `export const foo = require('foo');`
+        expr.visit_mut_children_with(self);
+    }
+
+    // export default function foo() {}
+    fn visit_mut_export_default_decl(&mut self, expr: &mut ExportDefaultDecl) {
+        self.add_local_export(DEFAULT_EXPORT_NAME, expr.span);
+        expr.visit_mut_children_with(self);
+    }
+
+    // export default foo;
+    fn visit_mut_export_default_expr(&mut self, expr: &mut ExportDefaultExpr) {
+        self.add_local_export(DEFAULT_EXPORT_NAME, expr.span);
+        expr.visit_mut_children_with(self);
+    }
+
+    // export = foo;
+    fn visit_mut_ts_export_assignment(&mut self, expr: &mut TsExportAssignment) {
+        // with `esModuleInterop` this is consumed as the default export
+        self.add_local_export(DEFAULT_EXPORT_NAME, expr.span);
+        expr.visit_mut_children_with(self);
+    }
+
+    // module.exports = foo;
+    // module.exports.foo = foo;
+    // exports.foo = foo;
+    fn visit_mut_assign_expr(&mut self, expr: &mut AssignExpr) {
+        let target = match &expr.left {
+            PatOrExpr::Expr(target) => Some(&**target),
+            PatOrExpr::Pat(pat) => match &**pat {
+                Pat::Expr(target) => Some(&**target),
+                _ => None,
+            },
+        };
+        let mut target_path = vec![];
+        let mut names = vec![];
+        if expr.op == AssignOp::Assign
+            && target.is_some_and(|target| get_callee_path(target, &mut target_path))
+        {
+            match target_path.as_slice() {
+                ["module", "exports"] => {
+                    // like `export =` this is consumed as the default export, and the properties of an object literal
+                    // can also be imported by name - eg `const { foo } = require('./foo')`
+                    names.push((DEFAULT_EXPORT_NAME.to_string(), expr.span));
+                    if let Expr::Object(object) = &*expr.right {
+                        for prop in object.props.iter() {
+                            if let PropOrSpread::Prop(prop) = prop {
+                                names.extend(get_prop_name(prop));
+                            }
+                        }
+                    }
+                }
+                ["module", "exports", name] | ["exports", name] => {
+                    names.push((name.to_string(), expr.span));
+                }
+                _ => {}
             }
         }
+        for (name, span) in names {
+            self.add_local_export(&name, span);
+        }
+
+        expr.visit_mut_children_with(self);
     }
 
     // import('foo')
     // require('foo')
     fn visit_mut_call_expr(&mut self, expr: &mut CallExpr) {
+        // imports are commonly nested within other calls - eg `React.lazy(() => import('./Foo'))`
+        expr.visit_mut_children_with(self);
+
         match &expr.callee {
             swc_ecma_ast::Callee::Import(_) => {
                 // import('foo')
-                self.get_dependency_for_call_like_expr("import", expr);
+                // call-like imports give the caller the entire module
+                self.get_dependency_for_call_like_expr(
+                    "import",
+                    expr,
+                    0,
+                    vec![ImportBinding::Namespace { local: None }],
+                );
             }
             swc_ecma_ast::Callee::Expr(callee) => match &**callee {
-                Expr::Ident(ident) => {
-                    if ident.sym == js_word!("require") {
-                        // require('foo')
-                        self.get_dependency_for_call_like_expr("require", expr);
-                    }
+                Expr::Ident(ident) if ident.sym == js_word!("require") => {
+                    // require('foo')
+                    self.get_dependency_for_call_like_expr(
+                        "require",
+                        expr,
+                        0,
+                        vec![ImportBinding::Namespace { local: None }],
+                    );
                 }
                 _ => {
-                    // random call expression which we ignore
+                    // jest.mock('foo')
+                    // require.resolve('foo')
+                    let mut callee_path = vec![];
+                    if get_callee_path(callee, &mut callee_path) {
+                        let callee_path = callee_path.join(".");
+                        let matched_pattern = self
+                            .call_patterns
+                            .iter()
+                            .find(|pattern| pattern.callee == callee_path);
+                        if let Some(pattern) = matched_pattern {
+                            // the module is only referenced - eg mocked - so none of its exports are used
+                            self.get_dependency_for_call_like_expr(
+                                &callee_path,
+                                expr,
+                                pattern.arg_index,
+                                vec![],
+                            );
+                        }
+                    }
+                    // otherwise it's a random call expression which we ignore
                 }
             },
             swc_ecma_ast::Callee::Super(_) => {
@@ -172,3 +651,154 @@ impl VisitMut for ImportVisitor {
         }
     }
 }
+
+fn get_module_export_name(name: &ModuleExportName) -> String {
+    return match name {
+        ModuleExportName::Ident(ident) => ident.sym.to_string(),
+        ModuleExportName::Str(str) => str.value.to_string(),
+    };
+}
+
+/// Gets the name of an object literal property - eg `foo` in `{ foo: 1 }`, `{ foo }` or `{ foo() {} }`
+fn get_prop_name(prop: &Prop) -> Option<(String, Span)> {
+    let key = match prop {
+        Prop::Shorthand(ident) => return Some((ident.sym.to_string(), ident.span)),
+        Prop::KeyValue(key_value) => &key_value.key,
+        Prop::Method(method) => &method.key,
+        _ => return None,
+    };
+    return match key {
+        PropName::Ident(ident) => Some((ident.sym.to_string(), ident.span)),
+        PropName::Str(str) => Some((str.value.to_string(), str.span)),
+        _ => None,
+    };
+}
+
+/// Gets the first name in a qualified name - eg `Foo` in `Foo.Bar.Baz`
+fn get_left_most_entity_name(name: &TsEntityName) -> &str {
+    return match name {
+        TsEntityName::Ident(ident) => &ident.sym,
+        TsEntityName::TsQualifiedName(qualified) => get_left_most_entity_name(&qualified.left),
+    };
+}
+
+/// Collects every name bound by a declaration pattern - eg `foo`, `bar` and `baz` in `const { foo, bar: [bar], ...baz } = x`
+fn get_pattern_binding_names(pat: &Pat, names: &mut Vec<(String, Span)>) {
+    match pat {
+        Pat::Ident(ident) => names.push((ident.id.sym.to_string(), ident.id.span)),
+        Pat::Array(array) => {
+            for elem in array.elems.iter().flatten() {
+                get_pattern_binding_names(elem, names);
+            }
+        }
+        Pat::Rest(rest) => get_pattern_binding_names(&rest.arg, names),
+        Pat::Object(object) => {
+            for prop in object.props.iter() {
+                match prop {
+                    ObjectPatProp::KeyValue(key_value) => {
+                        get_pattern_binding_names(&key_value.value, names)
+                    }
+                    ObjectPatProp::Assign(assign) => {
+                        names.push((assign.key.sym.to_string(), assign.key.span))
+                    }
+                    ObjectPatProp::Rest(rest) => get_pattern_binding_names(&rest.arg, names),
+                }
+            }
+        }
+        Pat::Assign(assign) => get_pattern_binding_names(&assign.left, names),
+        Pat::Invalid(_) | Pat::Expr(_) => {}
+    }
+}
+
+/// Flattens a callee into its dot-separated parts - eg `jest.mock` becomes `["jest", "mock"]`
+/// Returns false if the callee is not a simple, static member chain - eg `foo().bar` or `foo[bar]`
+fn get_callee_path<'a>(expr: &'a Expr, path: &mut Vec<&'a str>) -> bool {
+    match expr {
+        Expr::Ident(ident) => {
+            path.push(&ident.sym);
+            return true;
+        }
+        Expr::MetaProp(meta_prop) if meta_prop.kind == MetaPropKind::ImportMeta => {
+            path.push("import");
+            path.push("meta");
+            return true;
+        }
+        Expr::Member(member) => {
+            if !get_callee_path(&member.obj, path) {
+                return false;
+            }
+            match &member.prop {
+                MemberProp::Ident(ident) => {
+                    path.push(&ident.sym);
+                    return true;
+                }
+                MemberProp::Computed(computed) => match &*computed.expr {
+                    Expr::Lit(Lit::Str(str)) => {
+                        path.push(&str.value);
+                        return true;
+                    }
+                    _ => return false,
+                },
+                MemberProp::PrivateName(_) => return false,
+            }
+        }
+        Expr::Paren(paren) => return get_callee_path(&paren.expr, path),
+        _ => return false,
+    }
+}
+
+/// Flattens a string-like expression into its static and dynamic parts
+fn get_pattern_parts_for_expr(expr: &Expr, parts: &mut Vec<PatternPart>) {
+    match expr {
+        Expr::Lit(Lit::Str(str)) => push_static_part(parts, &str.value),
+        Expr::Tpl(tpl) => {
+            for (idx, quasi) in tpl.quasis.iter().enumerate() {
+                match &quasi.cooked {
+                    Some(cooked) => push_static_part(parts, cooked),
+                    None => push_static_part(parts, &quasi.raw),
+                }
+                if let Some(expr) = tpl.exprs.get(idx) {
+                    get_pattern_parts_for_expr(expr, parts);
+                }
+            }
+        }
+        Expr::Bin(bin) if bin.op == BinaryOp::Add => {
+            get_pattern_parts_for_expr(&bin.left, parts);
+            get_pattern_parts_for_expr(&bin.right, parts);
+        }
+        Expr::Paren(paren) => get_pattern_parts_for_expr(&paren.expr, parts),
+        _ => {
+            // consecutive dynamic parts collapse into a single wildcard
+            if parts.last() != Some(&PatternPart::Wildcard) {
+                parts.push(PatternPart::Wildcard);
+            }
+        }
+    }
+}
+
+fn push_static_part(parts: &mut Vec<PatternPart>, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    if let Some(PatternPart::Static(last)) = parts.last_mut() {
+        last.push_str(value);
+    } else {
+        parts.push(PatternPart::Static(value.to_string()));
+    }
+}
+
+/// Escapes any glob meta characters so that static parts of a pattern are matched literally
+pub fn escape_glob(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '*' | '?' | '[' | '{' | '}' => {
+                escaped.push('[');
+                escaped.push(c);
+                escaped.push(']');
+            }
+            _ => escaped.push(c),
+        }
+    }
+    return escaped;
+}
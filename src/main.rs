@@ -1,25 +1,89 @@
+mod affected;
+mod atomic_bit_set;
+mod breadth_first_expansion;
+mod call_pattern;
 mod cli;
+mod collapse;
+mod cycles;
+mod dead_files;
 mod dependency_graph;
 mod dependency_graph_store;
 mod depth_first_expansion;
+mod diagnostic;
 mod file_system;
+mod git;
+mod graph_cache;
+mod graph_diff;
+mod graph_export;
+mod html_report;
+mod import_path;
 mod import_visitor;
+mod metrics;
 mod module;
+mod output;
+mod package_dependencies;
+mod package_json;
 mod parser;
+mod public_api;
+mod rules;
+mod sarif;
+mod snapshot;
+#[cfg(test)]
+mod test_utils;
 mod tsconfig;
+mod unused_exports;
 
+use clap::ValueEnum;
+use notify::{event::ModifyKind, EventKind, RecursiveMode, Watcher};
 use petgraph::Direction;
 use rayon::prelude::*;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
 use std::io::{self, Write};
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use swc_common::{sync::Lrc, SourceMap};
 
-use crate::cli::parse_cli;
-use crate::dependency_graph::DependencyGraph;
-use crate::file_system::{get_files, path_parser_absolute};
-use crate::import_visitor::ImportVisitor;
+use crate::affected::{get_affected_files, get_tests_for_changes};
+use crate::call_pattern::CallPattern;
+use crate::cli::{
+    parse_cli, CliArgs, CliCommand, CliExportFormat, CliMetricsSort, CliTestListFormat, CliView,
+};
+use crate::collapse::collapse_graph;
+use crate::cycles::{get_cycles, Cycle};
+use crate::dead_files::get_dead_files;
+use crate::dependency_graph::{DependencyGraph, DiscoveredModule};
+use crate::file_system::{
+    build_glob_set, get_files, is_in_search_paths, path_parser_module, read_path_list,
+};
+use crate::git::{
+    get_changed_files, get_repository_root, parse_changed_files, try_get_repository_root,
+};
+use crate::graph_cache::{
+    get_parse_fingerprint, get_resolution_fingerprint, CachedFile, FileFingerprint, GraphCache,
+};
+use crate::graph_diff::diff_snapshots;
+use crate::graph_export::GraphExport;
+use crate::html_report::get_html_report;
+use crate::import_path::{get_import_paths, PathQuery};
+use crate::import_visitor::{Dependency, ImportVisitor, ParsedModule};
+use crate::metrics::get_metrics;
+use crate::module::{DependencyKind, ModuleKind};
+use crate::output::{Output, OutputImportHop, OutputRecord};
+use crate::package_dependencies::{get_package_dependency_issues, DependencyUsage};
+use crate::package_json::find_package_jsons;
 use crate::parser::parse_file;
-use crate::tsconfig::parse_tsconfig;
+use crate::public_api::get_deep_imports;
+use crate::rules::{check_rules, parse_rules_config};
+use crate::snapshot::GraphSnapshot;
+use crate::tsconfig::{parse_tsconfig, TSConfig};
+use crate::unused_exports::get_unused_exports;
+
+/// Set by `--quiet` to stop `measure!` and `print_timer!` from printing progress
+static QUIET: AtomicBool = AtomicBool::new(false);
 
 /// Simple macro to measure the time taken for an expression
 macro_rules! measure {
@@ -30,7 +94,9 @@ macro_rules! measure {
         (result, duration)
     }};
     ($start_label:literal, $e:expr) => {{
-        eprintln!($start_label);
+        if !QUIET.load(Ordering::Relaxed) {
+            eprintln!($start_label);
+        }
         let start = Instant::now();
         let result = { $e };
         let duration = start.elapsed();
@@ -39,99 +105,158 @@ macro_rules! measure {
 }
 macro_rules! print_timer {
     ($fmt:literal, $($arg:tt)*) => {{
-        let fmt = format!($fmt, $($arg)*);
-        eprintln!("⏲️  {}\n", &fmt);
+        if !QUIET.load(Ordering::Relaxed) {
+            let fmt = format!($fmt, $($arg)*);
+            eprintln!("⏲️  {}\n", &fmt);
+        }
     }};
 }
 
 fn main() {
     let args = parse_cli();
-    let (graph, duration) = measure!("Preparing dependency graph", {
-        let (tsconfig, duration) =
-            measure!("Parsing tsconfig...", parse_tsconfig(&args.tsconfig_path));
-        print_timer!("Parsed in {:?}", duration);
-
-        let (files, duration) = measure!("Getting file list...", get_files(&args.search_paths));
-        print_timer!("Found {} files in {:?}", files.len(), duration);
-
-        let mut raw_dependencies = Vec::with_capacity(files.len());
-        let (_, duration) = measure!(
-            "Parsing and extracting dependencies...",
-            files
-                .par_iter()
-                .map(|file| {
-                    let mut visitor = ImportVisitor::new();
-                    parse_file(file, &mut visitor);
-
-                    if !visitor.errors.is_empty() {
-                        eprintln!("❌ Errors for file {}:", file.display());
-                        for error in visitor.errors {
-                            eprintln!("❗️ {}", error);
-                        }
-                        eprintln!();
-                    }
-
-                    return (file, visitor.dependencies);
-                })
-                .collect_into_vec(&mut raw_dependencies)
-        );
-        print_timer!("Done in {:?}", duration);
+    QUIET.store(args.quiet, Ordering::Relaxed);
+    let output = Output::new(
+        args.format.into(),
+        args.relative_paths.then(get_relative_paths_root),
+    );
+    // watch builds and owns the graph itself so that it can keep it up to date
+    if let Some(CliCommand::Watch {
+        cycles,
+        ignore_type_only,
+    }) = &args.command
+    {
+        watch(&args, &output, *cycles, *ignore_type_only);
+        return;
+    }
 
-        let (mut graph, duration) = measure!(
-            "Preparing path -> module ID map",
-            DependencyGraph::new(&files, &tsconfig)
-        );
-        print_timer!("Done in {:?}", duration);
-
-        if let Some(dump_resolved_imports) = args.dump_resolved_imports {
-            let json = serde_json::to_string(&raw_dependencies)
-                .expect("Unable to serialize resolved imports");
-            let mut file = File::create(&dump_resolved_imports).expect(&format!(
-                "Unable to open file {:?}",
-                dump_resolved_imports.display()
-            ));
-            file.write_all(json.as_bytes())
-                .expect("Unable to write resolved imports");
+    let graph = match &args.graph {
+        Some(graph_path) => {
+            let (graph, duration) =
+                measure!("Loading graph", DependencyGraph::read_binary(graph_path));
+            print_timer!(
+                "Loaded {} modules in {:?}",
+                graph.get_module_ids().count(),
+                duration
+            );
+            graph
         }
-
-        let (resolution_errors, duration) = measure!(
-            "Resolving import strings and building dependency graph",
-            graph.resolve_imports(&raw_dependencies)
-        );
-        if let Some(resolution_errors) = resolution_errors {
-            for (file, errors) in resolution_errors.iter() {
-                eprintln!("❌ Errors for file {}:", file.display());
-                for error in errors {
-                    eprintln!("❗️ {}", error);
-                }
-                eprintln!();
-            }
+        None => {
+            let tsconfig = read_tsconfig(&args);
+            let (graph, duration) = measure!(
+                "Preparing dependency graph",
+                build_graph(&args, &tsconfig, &output)
+            );
+            print_timer!("Graph built in {:?}", duration);
+            graph
         }
-        print_timer!("Done in {:?}", duration);
+    };
 
-        graph
-    });
-    print_timer!("Graph built in {:?}", duration);
+    let grouping = args.get_grouping().expect("Invalid collapse group glob");
+    let graph = match &grouping {
+        Some(grouping) => {
+            let (collapsed_graph, duration) = measure!(
+                "Collapsing graph",
+                collapse_graph(&graph, grouping).expect("Unable to collapse graph")
+            );
+            print_timer!(
+                "Collapsed into {} modules in {:?}",
+                collapsed_graph.get_module_ids().count(),
+                duration
+            );
+            collapsed_graph
+        }
+        None => graph,
+    };
 
-    if let Some(file) = args.file {
-        let direction = args.direction;
-        let (maybe_dependencies, duration) = measure!(
-            "Fetching dependencies",
-            graph.get_all_dependencies(&file, direction.into(), args.max_depth)
-        );
-        match maybe_dependencies {
-            Ok(dependencies) => {
-                print_timer!(
-                    "Found {} dependencies in {:?}",
-                    dependencies.len(),
-                    duration
+    if let Some(command) = &args.command {
+        match command {
+            CliCommand::UnusedExports { entry } => print_unused_exports(&graph, &output, entry),
+            CliCommand::DeadFiles {
+                entry,
+                package_entry_points,
+            } => print_dead_files(&graph, &output, entry, *package_entry_points),
+            CliCommand::Cycles {
+                ignore_type_only,
+                members,
+            } => print_cycles(&graph, &output, *ignore_type_only, *members),
+            CliCommand::Affected { range, test } => {
+                print_affected_files(&graph, &output, range, test)
+            }
+            CliCommand::TestsFor {
+                range,
+                test,
+                format,
+            } => print_tests_for(&graph, &output, range, test, *format),
+            CliCommand::Snapshot { output } => write_snapshot(&graph, output),
+            CliCommand::SaveGraph { output } => save_graph(&graph, output),
+            CliCommand::Diff {
+                base,
+                head,
+                growth_threshold,
+                ignore_type_only,
+            } => print_graph_diff(
+                &graph,
+                &output,
+                base,
+                head,
+                *growth_threshold,
+                *ignore_type_only,
+            ),
+            CliCommand::CheckRules { config } => print_rule_violations(&graph, &output, config),
+            CliCommand::PublicApi => print_deep_imports(&graph, &output),
+            CliCommand::CheckDependencies => print_package_dependency_issues(&graph, &output),
+            CliCommand::Metrics { entry, sort, limit } => {
+                print_metrics(&graph, &output, entry, *sort, *limit)
+            }
+            CliCommand::Export { format, output } => {
+                let files = if args.file.is_empty() && args.files_from.is_none() {
+                    None
+                } else {
+                    Some(get_query_files(
+                        &graph,
+                        &args.file,
+                        args.files_from.as_deref(),
+                    ))
+                };
+                export_graph(
+                    &graph,
+                    files.as_deref(),
+                    args.direction.into(),
+                    args.max_depth,
+                    *format,
+                    output.as_deref(),
                 );
-                println!("{:#?}", dependencies);
             }
-            Err(e) => {
-                println!("Error getting dependencies {:?}", e);
+            CliCommand::Report { html } => write_html_report(&graph, html),
+            CliCommand::Watch { .. } => unreachable!("watch is handled before the graph is built"),
+            CliCommand::Path {
+                from,
+                to,
+                k,
+                max_length,
+            } => {
+                let query = match max_length {
+                    Some(max_length) => PathQuery::AllSimple(*max_length),
+                    None => PathQuery::Shortest(*k),
+                };
+                print_import_paths(&graph, &output, from, to, query);
             }
         }
+    } else if let Some(export_name) = &args.export_name {
+        match args.file.as_slice() {
+            [file] => print_importers_of_export(&graph, &output, file, export_name),
+            _ => println!("--export-name requires exactly one --file"),
+        }
+    } else if !args.file.is_empty() || args.files_from.is_some() {
+        let files = get_query_files(&graph, &args.file, args.files_from.as_deref());
+        let options = DependencyQueryOptions {
+            direction: args.direction.into(),
+            max_depth: args.max_depth,
+            view: args.view,
+            attribute_roots: args.attribute_roots,
+            show_import_counts: grouping.is_some(),
+        };
+        print_dependency_query(&graph, &output, &files, &options);
     } else {
         loop {
             println!("Enter file path (relative or absolute):");
@@ -154,25 +279,17 @@ fn main() {
                 break;
             }
 
-            match path_parser_absolute(&file_input) {
+            match path_parser_module(&file_input) {
                 Ok(file) => {
-                    let (maybe_dependencies, duration) = measure!(
-                        "Fetching dependencies",
-                        graph.get_all_dependencies(&file, direction, args.max_depth)
-                    );
-                    match maybe_dependencies {
-                        Ok(dependencies) => {
-                            print_timer!(
-                                "Found {} dependencies in {:?}",
-                                dependencies.len(),
-                                duration
-                            );
-                            println!("{:#?}", dependencies);
-                        }
-                        Err(e) => {
-                            println!("Error getting dependencies {:?}", e);
-                        }
-                    }
+                    let options = DependencyQueryOptions {
+                        direction,
+                        max_depth: args.max_depth,
+                        view: args.view,
+                        attribute_roots: args.attribute_roots,
+                        show_import_counts: grouping.is_some(),
+                    };
+                    let files = get_query_files(&graph, &[file], None);
+                    print_dependency_query(&graph, &output, &files, &options);
                 }
                 Err(e) => {
                     println!("Invalid path: {}", e);
@@ -184,7 +301,1639 @@ fn main() {
     }
 }
 
-fn read_line<'a>() -> Option<String> {
+fn read_tsconfig(args: &CliArgs) -> TSConfig {
+    let tsconfig_path = args
+        .tsconfig_path
+        .as_ref()
+        .expect("A tsconfig is required unless a graph is loaded");
+    let (tsconfig, duration) = measure!("Parsing tsconfig...", parse_tsconfig(tsconfig_path));
+    print_timer!("Parsed in {:?}", duration);
+    return tsconfig;
+}
+
+/// Parses the files in the search paths and resolves their imports
+fn build_graph(args: &CliArgs, tsconfig: &TSConfig, output: &Output) -> DependencyGraph {
+    let tsconfig_path = args
+        .tsconfig_path
+        .as_ref()
+        .expect("A tsconfig is required unless a graph is loaded");
+    let call_patterns = args.get_call_patterns();
+
+    let (files, duration) = measure!(
+        "Getting file list...",
+        get_files(&args.search_paths, output)
+    );
+    print_timer!("Found {} files in {:?}", files.len(), duration);
+
+    let parse_fingerprint = get_parse_fingerprint(&call_patterns);
+    let cache_path = args
+        .cache_dir
+        .as_ref()
+        .map(|cache_dir| GraphCache::get_path(cache_dir, &args.search_paths, tsconfig_path));
+    let mut cache = cache_path.as_ref().map(|cache_path| {
+        let (cache, duration) = measure!(
+            "Reading cache...",
+            GraphCache::read(cache_path, parse_fingerprint)
+        );
+        print_timer!("Read in {:?}", duration);
+        return cache;
+    });
+
+    let mut parsed_files = Vec::with_capacity(files.len());
+    let (_, duration) = measure!(
+        "Parsing and extracting dependencies...",
+        files
+            .par_iter()
+            .map(|file| {
+                let (parsed_module, cached_file) =
+                    parse_file_with_cache(file, &call_patterns, cache.as_ref(), output);
+                return (file, parsed_module, cached_file);
+            })
+            .collect_into_vec(&mut parsed_files)
+    );
+    let mut raw_dependencies = Vec::with_capacity(files.len());
+    let mut cached_files = HashMap::new();
+    for (file, parsed_module, cached_file) in parsed_files {
+        raw_dependencies.push((file, parsed_module));
+        if let Some(cached_file) = cached_file {
+            cached_files.insert(file.clone(), cached_file);
+        }
+    }
+    match &mut cache {
+        Some(cache) => {
+            let reused_count = cache.count_unchanged_files(&cached_files);
+            print_timer!(
+                "Parsed {} files and reused {} from the cache in {:?}",
+                files.len() - reused_count,
+                reused_count,
+                duration
+            );
+            cache.set_files(cached_files);
+        }
+        None => print_timer!("Done in {:?}", duration),
+    }
+
+    // bare imports may refer to a workspace package, so the resolver needs the package.jsons too
+    let has_bare_imports = raw_dependencies.iter().any(|(_, parsed_module)| {
+        return parsed_module.dependencies.iter().any(is_bare_import);
+    });
+    let package_jsons = if args.needs_package_jsons() || has_bare_imports {
+        find_package_jsons(&args.search_paths, output)
+    } else {
+        vec![]
+    };
+    let cached_resolved_imports = match &mut cache {
+        Some(cache) => {
+            cache.set_resolution_fingerprint(get_resolution_fingerprint(
+                &files,
+                tsconfig,
+                &package_jsons,
+            ));
+            cache.take_resolved_imports()
+        }
+        None => HashMap::new(),
+    };
+
+    let (mut graph, duration) = measure!(
+        "Preparing path -> module ID map",
+        DependencyGraph::new(&files, tsconfig, package_jsons)
+    );
+    print_timer!("Done in {:?}", duration);
+
+    if let Some(dump_resolved_imports) = &args.dump_resolved_imports {
+        let json =
+            serde_json::to_string(&raw_dependencies).expect("Unable to serialize resolved imports");
+        let mut file = File::create(dump_resolved_imports).expect(&format!(
+            "Unable to open file {:?}",
+            dump_resolved_imports.display()
+        ));
+        file.write_all(json.as_bytes())
+            .expect("Unable to write resolved imports");
+    }
+
+    let ((resolution_errors, resolved_imports), duration) = measure!(
+        "Resolving import strings and building dependency graph",
+        graph.resolve_imports(raw_dependencies, cached_resolved_imports)
+    );
+    if let Some(resolution_errors) = resolution_errors {
+        for (file, errors) in resolution_errors.iter() {
+            output.report_errors(file, errors);
+        }
+    }
+    print_timer!("Done in {:?}", duration);
+
+    if let (Some(mut cache), Some(cache_path)) = (cache, cache_path) {
+        cache.set_resolved_imports(resolved_imports);
+        let (result, duration) = measure!("Writing cache...", cache.write(&cache_path));
+        match result {
+            Ok(_) => print_timer!("Written in {:?}", duration),
+            // the graph is already built, so a cache which can't be saved only makes the next run slower
+            Err(e) => output.report_warning(e),
+        }
+    }
+
+    return graph;
+}
+
+/// Whether an import refers to a package by name rather than by path.
+/// eg `react` or `@scope/pkg/foo` rather than `./foo` or an absolute path from a tsconfig alias
+fn is_bare_import(dependency: &Dependency) -> bool {
+    let specifier = &dependency.specifier;
+    return dependency.kind != DependencyKind::WildcardDynamic
+        && !specifier.is_absolute()
+        && !specifier.starts_with("./")
+        && !specifier.starts_with("../");
+}
+
+/// Parses a file, or reuses its parsed imports from the cache if it hasn't changed.
+/// Returns the file's cache entry if there is a cache.
+fn parse_file_with_cache(
+    file: &Path,
+    call_patterns: &[CallPattern],
+    cache: Option<&GraphCache>,
+    output: &Output,
+) -> (ParsedModule, Option<CachedFile>) {
+    let fingerprint = cache.and_then(|_| FileFingerprint::new(file));
+    if let (Some(cache), Some(fingerprint)) = (cache, &fingerprint) {
+        if let Some(cached_file) = cache.get_file(file, fingerprint) {
+            output.report_errors(file, &cached_file.errors);
+            return (cached_file.parsed_module.clone(), Some(cached_file.clone()));
+        }
+    }
+
+    let source_map: Lrc<SourceMap> = Default::default();
+    let mut visitor = ImportVisitor::new(source_map.clone(), call_patterns);
+    parse_file(&source_map, file, &mut visitor);
+
+    output.report_errors(file, &visitor.errors);
+
+    let parsed_module = ParsedModule {
+        dependencies: visitor.dependencies,
+        exports: visitor.exports,
+    };
+    let cached_file = fingerprint.map(|fingerprint| CachedFile {
+        fingerprint,
+        parsed_module: parsed_module.clone(),
+        errors: visitor.errors,
+        resolved_imports: None,
+    });
+    return (parsed_module, cached_file);
+}
+
+/// Combines the `--file` and `--files-from` roots, skipping any which are not modules in the graph
+fn get_query_files(
+    graph: &DependencyGraph,
+    files: &[PathBuf],
+    files_from: Option<&Path>,
+) -> Vec<PathBuf> {
+    let mut query_files = files.to_vec();
+    if let Some(files_from) = files_from {
+        let paths = read_path_list(files_from)
+            .unwrap_or_else(|e| panic!("Unable to read file list {}: {}", files_from.display(), e));
+        for path in paths {
+            match path_parser_module(&path.to_string_lossy()) {
+                Ok(path) => query_files.push(path),
+                Err(e) => eprintln!("⚠️ Skipping {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    // in a collapsed graph a file is only a module through its group
+    for path in query_files.iter_mut() {
+        if let Some(group) = graph.get_group_for_file(path) {
+            *path = group.to_path_buf();
+        }
+    }
+    query_files.retain(|path| {
+        let is_module = graph.get_module_id_for_path(path).is_some();
+        if !is_module && !path.exists() {
+            eprintln!("⚠️ Skipping {} as it does not exist", path.display());
+        } else if !is_module {
+            eprintln!(
+                "⚠️ Skipping {} as it is not in the search paths",
+                path.display()
+            );
+        }
+        return is_module;
+    });
+    query_files.sort();
+    query_files.dedup();
+
+    return query_files;
+}
+
+struct DependencyQueryOptions {
+    direction: Direction,
+    max_depth: u32,
+    view: CliView,
+    /// Also find which of the roots reach each dependency
+    attribute_roots: bool,
+    /// Show the number of imports between each dependency and its parent, for collapsed graphs
+    show_import_counts: bool,
+}
+
+fn print_dependency_query(
+    graph: &DependencyGraph,
+    output: &Output,
+    files: &[PathBuf],
+    options: &DependencyQueryOptions,
+) {
+    let DependencyQueryOptions {
+        direction,
+        max_depth,
+        view,
+        attribute_roots,
+        show_import_counts,
+    } = *options;
+    let (maybe_dependencies, duration) = measure!(
+        "Fetching dependencies",
+        graph.get_all_dependencies(files, direction, max_depth)
+    );
+    let dependencies = match maybe_dependencies {
+        Ok(dependencies) => dependencies,
+        Err(e) => {
+            println!("Error getting dependencies {:?}", e);
+            return;
+        }
+    };
+    print_timer!(
+        "Found {} dependencies in {:?}",
+        dependencies.len(),
+        duration
+    );
+
+    let roots = if attribute_roots {
+        let (maybe_roots, duration) = measure!(
+            "Attributing dependencies to roots",
+            graph.get_roots_reaching_dependencies(files, direction, max_depth)
+        );
+        match maybe_roots {
+            Ok(roots) => {
+                print_timer!("Done in {:?}", duration);
+                Some(roots)
+            }
+            Err(e) => {
+                println!("Error attributing dependencies {:?}", e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    // in a collapsed graph a single edge between two groups can stand for many file imports
+    let import_counts = if show_import_counts {
+        let mut import_counts = HashMap::new();
+        for dependency in dependencies.iter() {
+            if let Some(parent) = &dependency.parent {
+                let maybe_count = match direction {
+                    Direction::Outgoing => graph.get_import_count(parent, &dependency.path),
+                    Direction::Incoming => graph.get_import_count(&dependency.path, parent),
+                };
+                match maybe_count {
+                    Ok(count) => {
+                        import_counts.insert(dependency.path.clone(), count);
+                    }
+                    Err(e) => {
+                        println!("Error counting imports {:?}", e);
+                        return;
+                    }
+                }
+            }
+        }
+        Some(import_counts)
+    } else {
+        None
+    };
+
+    if !output.is_text() {
+        let results = dependencies
+            .iter()
+            .map(|dependency| OutputRecord::Module {
+                path: output.path(&dependency.path),
+                kind: graph.get_module_kind(
+                    graph
+                        .get_module_id_for_path(&dependency.path)
+                        .expect("A discovered module should be in the graph"),
+                ),
+                depth: dependency.depth,
+                parent: dependency.parent.as_ref().map(|parent| output.path(parent)),
+                roots: roots
+                    .as_ref()
+                    .map(|roots| output.paths(roots.get(&dependency.path).map_or(&[], |r| r))),
+                import_count: import_counts
+                    .as_ref()
+                    .and_then(|counts| counts.get(&dependency.path).copied()),
+            })
+            .collect::<Vec<_>>();
+        let command = match direction {
+            Direction::Outgoing => "dependencies",
+            Direction::Incoming => "dependents",
+        };
+        output.write(command, &json!({ "maxDepth": max_depth }), files, &results);
+        return;
+    }
+
+    print_dependencies(&dependencies, view, roots.as_ref(), import_counts.as_ref());
+}
+
+fn print_importers_of_export(
+    graph: &DependencyGraph,
+    output: &Output,
+    file: &Path,
+    export_name: &str,
+) {
+    match graph.get_importers_of_export(file, export_name) {
+        Ok(importers) => {
+            if !output.is_text() {
+                let results = importers
+                    .iter()
+                    .map(|(path, location)| OutputRecord::Importer {
+                        path: output.path(path),
+                        line: location.line,
+                        column: location.column,
+                    })
+                    .collect::<Vec<_>>();
+                output.write(
+                    "importers",
+                    &json!({ "exportName": export_name }),
+                    &[file.to_path_buf()],
+                    &results,
+                );
+                return;
+            }
+
+            for (path, location) in importers {
+                println!("{}:{}:{}", path.display(), location.line, location.column);
+            }
+        }
+        Err(e) => {
+            println!("Error getting importers {:?}", e);
+        }
+    }
+}
+
+/// Prints the result of a traversal - modules are listed in the order they were discovered
+fn print_dependencies(
+    dependencies: &[DiscoveredModule],
+    view: CliView,
+    roots: Option<&HashMap<PathBuf, Vec<PathBuf>>>,
+    import_counts: Option<&HashMap<PathBuf, usize>>,
+) {
+    let format_annotations = |dependency: &DiscoveredModule| -> String {
+        let import_count = match import_counts.and_then(|counts| counts.get(&dependency.path)) {
+            Some(1) => String::from(" (1 import)"),
+            Some(count) => format!(" ({} imports)", count),
+            None => String::new(),
+        };
+        let roots = match roots.and_then(|roots| roots.get(&dependency.path)) {
+            // roots trivially reach themselves
+            Some(roots) if dependency.depth > 0 => format!(
+                " <- {}",
+                roots
+                    .iter()
+                    .map(|root| root.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => String::new(),
+        };
+        return format!("{}{}", import_count, roots);
+    };
+
+    match view {
+        CliView::Tree => {
+            let mut children: HashMap<&Path, Vec<&DiscoveredModule>> = HashMap::new();
+            for dependency in dependencies {
+                if let Some(parent) = &dependency.parent {
+                    children.entry(parent).or_default().push(dependency);
+                }
+            }
+
+            // the traversal only records each module once, so walking the parent links cannot loop
+            let mut stack = dependencies
+                .iter()
+                .filter(|dependency| dependency.parent.is_none())
+                .rev()
+                .collect::<Vec<_>>();
+            while let Some(dependency) = stack.pop() {
+                println!(
+                    "{}{}{}",
+                    "  ".repeat(dependency.depth as usize),
+                    dependency.path.display(),
+                    format_annotations(dependency)
+                );
+                if let Some(dependency_children) = children.get(dependency.path.as_path()) {
+                    stack.extend(dependency_children.iter().rev());
+                }
+            }
+        }
+        CliView::Layers => {
+            for layer in dependencies.chunk_by(|a, b| a.depth == b.depth) {
+                println!("Depth {} ({} modules):", layer[0].depth, layer.len());
+                for dependency in layer {
+                    println!(
+                        "  {}{}",
+                        dependency.path.display(),
+                        format_annotations(dependency)
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn print_unused_exports(graph: &DependencyGraph, output: &Output, entry: &[String]) {
+    let entry_points = build_glob_set(entry).expect("Invalid entry point glob");
+    let (maybe_unused_exports, duration) = measure!(
+        "Finding unused exports",
+        get_unused_exports(graph, &entry_points)
+    );
+    match maybe_unused_exports {
+        Ok(unused_exports) => {
+            print_timer!(
+                "Found unused exports in {} files in {:?}",
+                unused_exports.len(),
+                duration
+            );
+            if !output.is_text() {
+                let results = unused_exports
+                    .iter()
+                    .flat_map(|(path, exports)| {
+                        return exports.iter().map(|export| OutputRecord::UnusedExport {
+                            path: output.path(path),
+                            line: export.location.line,
+                            column: export.location.column,
+                            name: export.name.clone(),
+                        });
+                    })
+                    .collect::<Vec<_>>();
+                output.write("unused-exports", &json!({ "entry": entry }), &[], &results);
+                return;
+            }
+            for (path, exports) in unused_exports {
+                println!("{}", path.display());
+                for export in exports {
+                    println!(
+                        "  {}:{}  {}",
+                        export.location.line, export.location.column, export.name
+                    );
+                }
+            }
+        }
+        Err(e) => {
+            println!("Error getting unused exports {:?}", e);
+        }
+    }
+}
+
+fn print_dead_files(
+    graph: &DependencyGraph,
+    output: &Output,
+    entry: &[String],
+    package_entry_points: bool,
+) {
+    let entry_points = build_glob_set(entry).expect("Invalid entry point glob");
+    let package_jsons = if package_entry_points {
+        graph.get_package_jsons()
+    } else {
+        &[]
+    };
+    let (maybe_dead_files, duration) = measure!(
+        "Finding dead files",
+        get_dead_files(graph, &entry_points, package_jsons)
+    );
+    match maybe_dead_files {
+        Ok(dead_files) => {
+            for entry_point in dead_files.unresolved_entry_points.iter() {
+                output.report_warning(format!(
+                    "Unable to find a file for package entry point {}",
+                    entry_point.display()
+                ));
+            }
+            if dead_files.entry_point_count == 0 {
+                output.report_warning(String::from(
+                    "No entry points matched - every file will be reported as dead",
+                ));
+            }
+            print_timer!(
+                "Found {} dead files from {} entry points in {:?}",
+                dead_files.dead_files.len(),
+                dead_files.entry_point_count,
+                duration
+            );
+            if !output.is_text() {
+                let results = dead_files
+                    .dead_files
+                    .iter()
+                    .map(|path| OutputRecord::DeadFile {
+                        path: output.path(path),
+                    })
+                    .collect::<Vec<_>>();
+                output.write(
+                    "dead-files",
+                    &json!({ "entry": entry, "packageEntryPoints": package_entry_points }),
+                    &[],
+                    &results,
+                );
+                return;
+            }
+            for path in dead_files.dead_files {
+                println!("{}", path.display());
+            }
+        }
+        Err(e) => {
+            println!("Error getting dead files {:?}", e);
+        }
+    }
+}
+
+fn print_cycles(graph: &DependencyGraph, output: &Output, ignore_type_only: bool, members: bool) {
+    let (maybe_cycles, duration) = measure!("Finding cycles", get_cycles(graph, ignore_type_only));
+    match maybe_cycles {
+        Ok(cycles) => {
+            print_timer!("Found {} cycles in {:?}", cycles.len(), duration);
+            if !output.is_text() {
+                let results = cycles
+                    .iter()
+                    .map(|cycle| OutputRecord::Cycle {
+                        modules: output.paths(&cycle.modules),
+                        shortest_loop: output.paths(&cycle.shortest_loop),
+                    })
+                    .collect::<Vec<_>>();
+                output.write(
+                    "cycles",
+                    &json!({ "ignoreTypeOnly": ignore_type_only }),
+                    &[],
+                    &results,
+                );
+                return;
+            }
+            for (idx, cycle) in cycles.iter().enumerate() {
+                println!(
+                    "Cycle {} ({} modules, shortest loop has {} imports):",
+                    idx + 1,
+                    cycle.modules.len(),
+                    cycle.shortest_loop.len() - 1
+                );
+                for (loop_idx, path) in cycle.shortest_loop.iter().enumerate() {
+                    let prefix = if loop_idx == 0 { "   " } else { "-> " };
+                    println!("  {}{}", prefix, path.display());
+                }
+                if members {
+                    println!("  Members:");
+                    for path in cycle.modules.iter() {
+                        println!("    {}", path.display());
+                    }
+                }
+                println!();
+            }
+        }
+        Err(e) => {
+            println!("Error getting cycles {:?}", e);
+        }
+    }
+}
+
+/// Lists the changed files from a git revision range, or from a git diff read from stdin
+fn read_changed_files(range: &Option<String>) -> Vec<PathBuf> {
+    return match range {
+        Some(range) => get_changed_files(range),
+        None => {
+            let output = io::read_to_string(io::stdin()).expect("Unable to read stdin");
+            parse_changed_files(&output, &get_repository_root())
+        }
+    };
+}
+
+fn print_affected_files(
+    graph: &DependencyGraph,
+    output: &Output,
+    range: &Option<String>,
+    test: &[String],
+) {
+    let tests = build_glob_set(test).expect("Invalid test glob");
+    let changed_files = read_changed_files(range);
+
+    let (maybe_affected_files, duration) = measure!(
+        "Finding affected files",
+        get_affected_files(graph, &changed_files, &tests)
+    );
+    match maybe_affected_files {
+        Ok(affected_files) => {
+            print_timer!(
+                "Found {} affected files and {} affected tests from {} changed files in {:?}",
+                affected_files.affected_files.len(),
+                affected_files.affected_tests.len(),
+                changed_files.len(),
+                duration
+            );
+
+            if !output.is_text() {
+                let results = affected_files
+                    .unmatched_changes
+                    .iter()
+                    .map(|path| OutputRecord::UnmatchedChange {
+                        path: output.path(path),
+                    })
+                    .chain(affected_files.affected_files.iter().map(|path| {
+                        OutputRecord::AffectedFile {
+                            path: output.path(path),
+                            is_test: false,
+                        }
+                    }))
+                    .chain(affected_files.affected_tests.iter().map(|path| {
+                        OutputRecord::AffectedFile {
+                            path: output.path(path),
+                            is_test: true,
+                        }
+                    }))
+                    .collect::<Vec<_>>();
+                output.write(
+                    "affected",
+                    &json!({ "range": range, "test": test }),
+                    &changed_files,
+                    &results,
+                );
+                return;
+            }
+
+            if !affected_files.unmatched_changes.is_empty() {
+                eprintln!("❗️ These changed files are not imported by any module:");
+                for path in affected_files.unmatched_changes.iter() {
+                    eprintln!("  {}", path.display());
+                }
+            }
+            println!("Affected files:");
+            for path in affected_files.affected_files.iter() {
+                println!("  {}", path.display());
+            }
+            println!("Affected tests:");
+            for path in affected_files.affected_tests.iter() {
+                println!("  {}", path.display());
+            }
+        }
+        Err(e) => {
+            println!("Error finding affected files {:?}", e);
+        }
+    }
+}
+
+fn print_tests_for(
+    graph: &DependencyGraph,
+    output: &Output,
+    range: &Option<String>,
+    test: &[String],
+    format: CliTestListFormat,
+) {
+    let tests = build_glob_set(test).expect("Invalid test glob");
+    let changed_files = read_changed_files(range);
+
+    let (maybe_test_selection, duration) = measure!(
+        "Finding tests for changed files",
+        get_tests_for_changes(graph, &changed_files, &tests)
+    );
+    let test_selection = match maybe_test_selection {
+        Ok(test_selection) => test_selection,
+        Err(e) => {
+            println!("Error finding tests {:?}", e);
+            return;
+        }
+    };
+    print_timer!(
+        "Found {} tests from {} changed files in {:?}",
+        test_selection.tests.len(),
+        changed_files.len(),
+        duration
+    );
+
+    if !output.is_text() {
+        let results = test_selection
+            .unmatched_changes
+            .iter()
+            .map(|path| OutputRecord::UnmatchedChange {
+                path: output.path(path),
+            })
+            .chain(test_selection.uncovered_changes.iter().map(|path| {
+                OutputRecord::UncoveredChange {
+                    path: output.path(path),
+                }
+            }))
+            .chain(test_selection.tests.iter().map(|path| OutputRecord::Test {
+                path: output.path(path),
+            }))
+            .collect::<Vec<_>>();
+        output.write(
+            "tests-for",
+            &json!({ "range": range, "test": test }),
+            &changed_files,
+            &results,
+        );
+        return;
+    }
+
+    // stdout is reserved for the test list so that it can be passed straight to a test runner
+    if !test_selection.unmatched_changes.is_empty() {
+        eprintln!("❗️ These changed files are not imported by any module:");
+        for path in test_selection.unmatched_changes.iter() {
+            eprintln!("  {}", path.display());
+        }
+    }
+    if !test_selection.uncovered_changes.is_empty() {
+        eprintln!("❗️ These changed files are not covered by any test:");
+        for path in test_selection.uncovered_changes.iter() {
+            eprintln!("  {}", path.display());
+        }
+    }
+
+    let current_dir = std::env::current_dir().expect("Unable to read the current directory");
+    let get_relative_path = |path: &PathBuf| -> String {
+        return path
+            .strip_prefix(&current_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string();
+    };
+    match format {
+        CliTestListFormat::Lines => {
+            for path in test_selection.tests.iter() {
+                println!("{}", path.display());
+            }
+        }
+        CliTestListFormat::Jest => {
+            // jest treats each argument as a regex which is matched against the absolute test path
+            println!(
+                "{}",
+                test_selection
+                    .tests
+                    .iter()
+                    .map(|path| quote_shell_arg(&escape_regex(&get_relative_path(path))))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+        CliTestListFormat::Vitest => {
+            // vitest treats each argument as a substring which is matched against the test path
+            println!(
+                "{}",
+                test_selection
+                    .tests
+                    .iter()
+                    .map(|path| quote_shell_arg(&get_relative_path(path)))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+    }
+}
+
+/// Single quotes an argument if the shell (or xargs) would otherwise split it or interpret it - eg a path containing a
+/// space or a regex containing `\`
+fn quote_shell_arg(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+,:@%=".contains(c))
+    {
+        return arg.to_string();
+    }
+    return format!("'{}'", arg.replace('\'', "'\\''"));
+}
+
+fn escape_regex(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    return escaped;
+}
+
+/// The directory relative paths are written from - the repository root rather than the current directory, so that
+/// running from different subdirectories gives the same paths.
+/// Outside of a repository the current directory is used instead.
+fn get_relative_paths_root() -> PathBuf {
+    return try_get_repository_root()
+        .unwrap_or_else(|| std::env::current_dir().expect("Unable to read the current directory"));
+}
+
+fn create_snapshot(graph: &DependencyGraph) -> GraphSnapshot {
+    return GraphSnapshot::new(graph, &get_relative_paths_root())
+        .expect("Unable to snapshot the graph");
+}
+
+fn write_snapshot(graph: &DependencyGraph, output: &Path) {
+    let (_, duration) = measure!("Writing snapshot", create_snapshot(graph).write(output));
+    print_timer!("Wrote {} in {:?}", output.display(), duration);
+}
+
+fn save_graph(graph: &DependencyGraph, output: &Path) {
+    let (result, duration) = measure!("Saving graph", graph.write_binary(output));
+    match result {
+        Ok(()) => print_timer!("Wrote {} in {:?}", output.display(), duration),
+        Err(e) => {
+            eprintln!("❗️ {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn export_graph(
+    graph: &DependencyGraph,
+    files: Option<&[PathBuf]>,
+    direction: Direction,
+    max_depth: u32,
+    format: CliExportFormat,
+    output: Option<&Path>,
+) {
+    let module_ids = match files {
+        Some(files) => match graph.get_all_dependencies(files, direction, max_depth) {
+            Ok(dependencies) => Some(
+                dependencies
+                    .iter()
+                    .filter_map(|dependency| graph.get_module_id_for_path(&dependency.path))
+                    .collect::<HashSet<_>>(),
+            ),
+            Err(e) => {
+                println!("Error getting dependencies {:?}", e);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let current_dir = std::env::current_dir().expect("Unable to read the current directory");
+    let (export, duration) = measure!(
+        "Preparing export",
+        GraphExport::new(graph, module_ids.as_ref(), &current_dir)
+            .expect("Unable to export the graph")
+    );
+    print_timer!(
+        "Prepared {} modules and {} imports in {:?}",
+        export.nodes.len(),
+        export.edges.len(),
+        duration
+    );
+
+    let result = match output {
+        Some(output) => {
+            let file = File::create(output)
+                .unwrap_or_else(|_| panic!("Unable to create {}", output.display()));
+            export.write(format.into(), &mut io::BufWriter::new(file))
+        }
+        None => export.write(format.into(), &mut io::stdout().lock()),
+    };
+    result.expect("Unable to write the graph");
+}
+
+fn write_html_report(graph: &DependencyGraph, output: &Path) {
+    let current_dir = std::env::current_dir().expect("Unable to read the current directory");
+    let (report, duration) = measure!(
+        "Building report",
+        get_html_report(graph, &current_dir).expect("Unable to build the report")
+    );
+    std::fs::write(output, report)
+        .unwrap_or_else(|e| panic!("Unable to write {}: {}", output.display(), e));
+    print_timer!("Wrote {} in {:?}", output.display(), duration);
+}
+
+fn print_graph_diff(
+    graph: &DependencyGraph,
+    output: &Output,
+    base: &Path,
+    head: &Option<PathBuf>,
+    growth_threshold: usize,
+    ignore_type_only: bool,
+) {
+    let base = GraphSnapshot::read(base);
+    let head = match head {
+        Some(head) => GraphSnapshot::read(head),
+        None => create_snapshot(graph),
+    };
+
+    let (diff, duration) = measure!(
+        "Comparing graphs",
+        diff_snapshots(&base, &head, growth_threshold, ignore_type_only)
+    );
+    print_timer!(
+        "Found {} added and {} removed imports in {:?}",
+        diff.added_edges.len(),
+        diff.removed_edges.len(),
+        duration
+    );
+
+    if !output.is_text() {
+        // snapshot paths are already relative to the repository root
+        let results = diff
+            .added_edges
+            .iter()
+            .map(|(from, to)| OutputRecord::AddedImport {
+                from: from.clone(),
+                to: to.clone(),
+            })
+            .chain(
+                diff.removed_edges
+                    .iter()
+                    .map(|(from, to)| OutputRecord::RemovedImport {
+                        from: from.clone(),
+                        to: to.clone(),
+                    }),
+            )
+            .chain(diff.new_cycles.iter().map(|cycle| OutputRecord::NewCycle {
+                modules: cycle.modules.clone(),
+                shortest_loop: cycle.shortest_loop.clone(),
+            }))
+            .chain(
+                diff.new_packages
+                    .iter()
+                    .map(|name| OutputRecord::NewPackage { name: name.clone() }),
+            )
+            .chain(
+                diff.dependency_growth
+                    .iter()
+                    .map(|growth| OutputRecord::DependencyGrowth {
+                        path: growth.path.clone(),
+                        base_count: growth.base_count,
+                        head_count: growth.head_count,
+                    }),
+            )
+            .collect::<Vec<_>>();
+        output.write(
+            "diff",
+            &json!({
+                "growthThreshold": growth_threshold,
+                "ignoreTypeOnly": ignore_type_only,
+            }),
+            &[],
+            &results,
+        );
+        return;
+    }
+
+    println!("Added imports ({}):", diff.added_edges.len());
+    for (from, to) in diff.added_edges.iter() {
+        println!("  {} -> {}", from.display(), to.display());
+    }
+    println!("Removed imports ({}):", diff.removed_edges.len());
+    for (from, to) in diff.removed_edges.iter() {
+        println!("  {} -> {}", from.display(), to.display());
+    }
+    println!("New cycles ({}):", diff.new_cycles.len());
+    for cycle in diff.new_cycles.iter() {
+        println!(
+            "  {} modules: {}",
+            cycle.modules.len(),
+            cycle
+                .shortest_loop
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        );
+    }
+    println!("New packages ({}):", diff.new_packages.len());
+    for package in diff.new_packages.iter() {
+        println!("  {}", package.display());
+    }
+    println!(
+        "Transitive dependency growth over {} ({}):",
+        growth_threshold,
+        diff.dependency_growth.len()
+    );
+    for growth in diff.dependency_growth.iter() {
+        println!(
+            "  {} {} -> {} (+{})",
+            growth.path.display(),
+            growth.base_count,
+            growth.head_count,
+            growth.head_count - growth.base_count
+        );
+    }
+}
+
+fn print_rule_violations(graph: &DependencyGraph, output: &Output, config: &Path) {
+    let rules = parse_rules_config(config);
+    let (maybe_violations, duration) = measure!("Checking rules", check_rules(graph, &rules));
+    match maybe_violations {
+        Ok(violations) => {
+            print_timer!(
+                "Found {} violations of {} rules in {:?}",
+                violations.len(),
+                rules.len(),
+                duration
+            );
+            if !output.is_text() {
+                let results = violations
+                    .iter()
+                    .map(|violation| OutputRecord::RuleViolation {
+                        path: output.path(&violation.path),
+                        line: violation.location.line,
+                        column: violation.location.column,
+                        rule: violation.rule.name.clone(),
+                        specifier: violation.specifier.clone(),
+                        message: violation.rule.message.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                output.write(
+                    "check-rules",
+                    &json!({ "config": output.path(config) }),
+                    &[],
+                    &results,
+                );
+            } else {
+                for violation in violations.iter() {
+                    println!(
+                        "{}:{}:{} [{}] import of \"{}\" is not allowed{}",
+                        violation.path.display(),
+                        violation.location.line,
+                        violation.location.column,
+                        violation.rule.name,
+                        violation.specifier.display(),
+                        match &violation.rule.message {
+                            Some(message) => format!(" - {}", message),
+                            None => String::new(),
+                        }
+                    );
+                }
+            }
+            if !violations.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            println!("Error checking rules {:?}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_deep_imports(graph: &DependencyGraph, output: &Output) {
+    let (maybe_deep_imports, duration) = measure!("Finding deep imports", get_deep_imports(graph));
+    match maybe_deep_imports {
+        Ok(deep_imports) => {
+            print_timer!(
+                "Found {} deep imports in {:?}",
+                deep_imports.len(),
+                duration
+            );
+            if !output.is_text() {
+                let results = deep_imports
+                    .iter()
+                    .map(|deep_import| OutputRecord::DeepImport {
+                        path: output.path(&deep_import.path),
+                        line: deep_import.location.line,
+                        column: deep_import.location.column,
+                        specifier: deep_import.specifier.clone(),
+                        package: deep_import.package_name.clone(),
+                        suggestion: deep_import.suggestion.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                output.write("public-api", &json!({}), &[], &results);
+            } else {
+                for deep_import in deep_imports.iter() {
+                    println!(
+                        "{}:{}:{} import of \"{}\" is not part of the public API of {}{}",
+                        deep_import.path.display(),
+                        deep_import.location.line,
+                        deep_import.location.column,
+                        deep_import.specifier.display(),
+                        deep_import.package_name,
+                        match &deep_import.suggestion {
+                            Some(suggestion) =>
+                                format!(" - import from \"{}\" instead", suggestion),
+                            None => String::new(),
+                        }
+                    );
+                }
+            }
+            if !deep_imports.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            println!("Error finding deep imports {:?}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_package_dependency_issues(graph: &DependencyGraph, output: &Output) {
+    let (maybe_issues, duration) = measure!(
+        "Checking package dependencies",
+        get_package_dependency_issues(graph)
+    );
+    let issues = match maybe_issues {
+        Ok(issues) => issues,
+        Err(e) => {
+            println!("Error checking package dependencies {:?}", e);
+            std::process::exit(1);
+        }
+    };
+    print_timer!(
+        "Found issues in {} packages in {:?}",
+        issues.len(),
+        duration
+    );
+
+    if !output.is_text() {
+        let mut results = vec![];
+        for package_issues in issues.iter() {
+            let package_json = output.path(&package_issues.package_json);
+            results.extend(package_issues.missing.iter().map(|usage| {
+                OutputRecord::MissingDependency {
+                    package_json: package_json.clone(),
+                    name: usage.name.clone(),
+                    path: output.path(&usage.path),
+                    line: usage.location.line,
+                    column: usage.location.column,
+                    import_count: usage.import_count,
+                }
+            }));
+            results.extend(package_issues.unused.iter().map(|name| {
+                OutputRecord::UnusedDependency {
+                    package_json: package_json.clone(),
+                    name: name.clone(),
+                }
+            }));
+            results.extend(package_issues.misplaced.iter().map(|usage| {
+                OutputRecord::MisplacedDependency {
+                    package_json: package_json.clone(),
+                    name: usage.name.clone(),
+                    path: output.path(&usage.path),
+                    line: usage.location.line,
+                    column: usage.location.column,
+                    import_count: usage.import_count,
+                }
+            }));
+        }
+        output.write("check-dependencies", &json!({}), &[], &results);
+        if !issues.is_empty() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let print_usages = |title: &str, usages: &[DependencyUsage]| {
+        if usages.is_empty() {
+            return;
+        }
+        println!("  {}:", title);
+        for usage in usages {
+            println!(
+                "    {} - imported by {}:{}:{}{}",
+                usage.name,
+                usage.path.display(),
+                usage.location.line,
+                usage.location.column,
+                if usage.import_count > 1 {
+                    format!(" and {} more", usage.import_count - 1)
+                } else {
+                    String::new()
+                }
+            );
+        }
+    };
+    for package_issues in issues.iter() {
+        println!("{}", package_issues.package_json.display());
+        print_usages("Missing dependencies", &package_issues.missing);
+        if !package_issues.unused.is_empty() {
+            println!("  Unused dependencies:");
+            for name in package_issues.unused.iter() {
+                println!("    {}", name);
+            }
+        }
+        print_usages(
+            "Dev dependencies used at runtime",
+            &package_issues.misplaced,
+        );
+    }
+
+    if !issues.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn print_metrics(
+    graph: &DependencyGraph,
+    output: &Output,
+    entry: &[String],
+    sort: CliMetricsSort,
+    limit: Option<usize>,
+) {
+    let entry_points = build_glob_set(entry).expect("Invalid entry point glob");
+    let (maybe_metrics, duration) =
+        measure!("Computing metrics", get_metrics(graph, &entry_points));
+    let mut metrics = match maybe_metrics {
+        Ok(metrics) => metrics,
+        Err(e) => {
+            println!("Error computing metrics {:?}", e);
+            return;
+        }
+    };
+    print_timer!(
+        "Computed metrics for {} files in {:?}",
+        metrics.len(),
+        duration
+    );
+
+    metrics.sort_by(|a, b| {
+        let ordering = match sort {
+            CliMetricsSort::FanIn => b.fan_in.cmp(&a.fan_in),
+            CliMetricsSort::FanOut => b.fan_out.cmp(&a.fan_out),
+            CliMetricsSort::TransitiveDependencies => {
+                b.transitive_dependencies.cmp(&a.transitive_dependencies)
+            }
+            CliMetricsSort::TransitiveDependents => {
+                b.transitive_dependents.cmp(&a.transitive_dependents)
+            }
+            CliMetricsSort::Instability => b.instability.total_cmp(&a.instability),
+            CliMetricsSort::Depth => b.depth.cmp(&a.depth),
+            CliMetricsSort::TransitiveBytes => b.transitive_bytes.cmp(&a.transitive_bytes),
+            CliMetricsSort::Path => a.path.cmp(&b.path),
+        };
+        return ordering.then_with(|| a.path.cmp(&b.path));
+    });
+    if let Some(limit) = limit {
+        metrics.truncate(limit);
+    }
+
+    if !output.is_text() {
+        let results = metrics
+            .into_iter()
+            .map(|mut module_metrics| {
+                module_metrics.path = output.path(&module_metrics.path);
+                return OutputRecord::Metrics(module_metrics);
+            })
+            .collect::<Vec<_>>();
+        output.write(
+            "metrics",
+            &json!({
+                "entry": entry,
+                "sort": sort.to_possible_value().map(|value| value.get_name().to_string()),
+                "limit": limit,
+            }),
+            &[],
+            &results,
+        );
+        return;
+    }
+
+    println!(
+        "{:>8} {:>8} {:>8} {:>10} {:>11} {:>6} {:>12}  path",
+        "fan-in", "fan-out", "deps", "dependents", "instability", "depth", "bytes"
+    );
+    for module_metrics in metrics.iter() {
+        println!(
+            "{:>8} {:>8} {:>8} {:>10} {:>11.2} {:>6} {:>12}  {}",
+            module_metrics.fan_in,
+            module_metrics.fan_out,
+            module_metrics.transitive_dependencies,
+            module_metrics.transitive_dependents,
+            module_metrics.instability,
+            module_metrics
+                .depth
+                .map_or(String::from("-"), |depth| depth.to_string()),
+            module_metrics.transitive_bytes,
+            module_metrics.path.display()
+        );
+    }
+}
+
+fn print_import_paths(
+    graph: &DependencyGraph,
+    output: &Output,
+    from: &Path,
+    to: &Path,
+    query: PathQuery,
+) {
+    let (maybe_paths, duration) = measure!(
+        "Finding import paths",
+        get_import_paths(graph, from, to, query)
+    );
+    match maybe_paths {
+        Ok(paths) => {
+            print_timer!("Found {} paths in {:?}", paths.len(), duration);
+            if !output.is_text() {
+                let results = paths
+                    .iter()
+                    .map(|path| OutputRecord::ImportPath {
+                        hops: path
+                            .hops
+                            .iter()
+                            .map(|hop| OutputImportHop {
+                                from: output.path(&hop.from),
+                                line: hop.location.line,
+                                column: hop.location.column,
+                                specifier: hop.specifier.clone(),
+                            })
+                            .collect(),
+                        to: output.path(&path.to),
+                    })
+                    .collect::<Vec<_>>();
+                output.write(
+                    "path",
+                    &match query {
+                        PathQuery::Shortest(k) => json!({ "to": output.path(to), "k": k }),
+                        PathQuery::AllSimple(max_length) => {
+                            json!({ "to": output.path(to), "maxLength": max_length })
+                        }
+                    },
+                    &[from.to_path_buf()],
+                    &results,
+                );
+                return;
+            }
+            if paths.is_empty() {
+                println!("{} does not depend on {}", from.display(), to.display());
+            }
+            for (idx, path) in paths.iter().enumerate() {
+                println!("Path {} ({} imports):", idx + 1, path.hops.len());
+                for hop in path.hops.iter() {
+                    println!(
+                        "  {}:{}:{} imports \"{}\"",
+                        hop.from.display(),
+                        hop.location.line,
+                        hop.location.column,
+                        hop.specifier.display()
+                    );
+                }
+                println!("  {}", path.to.display());
+                println!();
+            }
+        }
+        Err(e) => {
+            println!("Error getting import paths {:?}", e);
+        }
+    }
+}
+
+/// How long to wait for more file events before updating the graph - a single save or checkout creates a burst of
+/// events which are best handled together
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// A query which is printed again whenever a change to the files changes its results
+enum WatchQuery {
+    Dependencies {
+        files: Vec<PathBuf>,
+        options: DependencyQueryOptions,
+    },
+    Cycles {
+        ignore_type_only: bool,
+    },
+}
+
+/// The results of a `WatchQuery`, which are compared to find out whether they have changed
+#[derive(PartialEq)]
+enum WatchResults {
+    Dependencies(Vec<DiscoveredModule>, Option<Vec<(PathBuf, Vec<PathBuf>)>>),
+    Cycles(Vec<Cycle>),
+    Error(String),
+}
+
+impl WatchQuery {
+    /// The query's files which are currently in the graph - they can be deleted and created again while watching
+    fn get_files(&self, graph: &DependencyGraph) -> Vec<PathBuf> {
+        return match self {
+            WatchQuery::Dependencies { files, .. } => files
+                .iter()
+                .filter(|file| graph.get_module_id_for_path(file).is_some())
+                .cloned()
+                .collect(),
+            WatchQuery::Cycles { .. } => vec![],
+        };
+    }
+
+    fn get_results(&self, graph: &DependencyGraph) -> WatchResults {
+        return match self {
+            WatchQuery::Dependencies { options, .. } => {
+                let files = self.get_files(graph);
+                let mut dependencies = match graph.get_all_dependencies(
+                    &files,
+                    options.direction,
+                    options.max_depth,
+                ) {
+                    Ok(dependencies) => dependencies,
+                    Err(e) => return WatchResults::Error(e.to_string()),
+                };
+                // the traversal order depends on the order the edges were added in, which changes as files are updated
+                dependencies.sort_by(|a, b| a.path.cmp(&b.path));
+                let roots = if options.attribute_roots {
+                    match graph.get_roots_reaching_dependencies(
+                        &files,
+                        options.direction,
+                        options.max_depth,
+                    ) {
+                        Ok(roots) => {
+                            let mut roots = roots.into_iter().collect::<Vec<_>>();
+                            roots.sort();
+                            Some(roots)
+                        }
+                        Err(e) => return WatchResults::Error(e.to_string()),
+                    }
+                } else {
+                    None
+                };
+                WatchResults::Dependencies(dependencies, roots)
+            }
+            WatchQuery::Cycles { ignore_type_only } => match get_cycles(graph, *ignore_type_only) {
+                Ok(cycles) => WatchResults::Cycles(cycles),
+                Err(e) => WatchResults::Error(e.to_string()),
+            },
+        };
+    }
+
+    fn print(&self, graph: &DependencyGraph, output: &Output) {
+        match self {
+            WatchQuery::Dependencies { options, .. } => {
+                print_dependency_query(graph, output, &self.get_files(graph), options)
+            }
+            WatchQuery::Cycles { ignore_type_only } => {
+                print_cycles(graph, output, *ignore_type_only, false)
+            }
+        }
+    }
+}
+
+/// Builds the graph, then updates it as files in the search paths change - printing the queries again whenever their
+/// results change
+fn watch(args: &CliArgs, output: &Output, cycles: bool, ignore_type_only: bool) {
+    let grouping = args.get_grouping().expect("Invalid collapse group glob");
+    if args.graph.is_some() || grouping.is_some() {
+        eprintln!("❗️ watch cannot be used with --graph or the --collapse options");
+        std::process::exit(1);
+    }
+
+    let tsconfig = read_tsconfig(args);
+    let call_patterns = args.get_call_patterns();
+    let (mut graph, duration) = measure!(
+        "Preparing dependency graph",
+        build_graph(args, &tsconfig, output)
+    );
+    print_timer!("Graph built in {:?}", duration);
+
+    let mut queries = vec![];
+    if !args.file.is_empty() || args.files_from.is_some() {
+        queries.push(WatchQuery::Dependencies {
+            files: get_query_files(&graph, &args.file, args.files_from.as_deref()),
+            options: DependencyQueryOptions {
+                direction: args.direction.into(),
+                max_depth: args.max_depth,
+                view: args.view,
+                attribute_roots: args.attribute_roots,
+                show_import_counts: false,
+            },
+        });
+    }
+    if cycles {
+        queries.push(WatchQuery::Cycles { ignore_type_only });
+    }
+    let mut query_results = queries
+        .iter()
+        .map(|query| {
+            query.print(&graph, output);
+            return query.get_results(&graph);
+        })
+        .collect::<Vec<_>>();
+
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).expect("Unable to watch for changes");
+    for search_path in args.search_paths.iter() {
+        watcher
+            .watch(search_path, RecursiveMode::Recursive)
+            .unwrap_or_else(|e| panic!("Unable to watch {}: {}", search_path.display(), e));
+    }
+    if !QUIET.load(Ordering::Relaxed) {
+        eprintln!("👀 Watching for changes...\n");
+    }
+
+    while let Ok(event) = receiver.recv() {
+        let mut changed_paths = BTreeSet::new();
+        let mut created_directories = BTreeSet::new();
+        let mut next_event = Some(event);
+        while let Some(event) = next_event {
+            match event {
+                // reads never change the graph - and include our own reads of the changed files
+                Ok(event) if matches!(event.kind, EventKind::Access(_)) => {}
+                Ok(event) => {
+                    let is_created = matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_))
+                    );
+                    for path in event.paths {
+                        if is_created && path.is_dir() {
+                            created_directories.insert(path);
+                        } else {
+                            changed_paths.insert(path);
+                        }
+                    }
+                }
+                Err(e) => output.report_warning(format!("Unable to watch for changes: {}", e)),
+            }
+            next_event = receiver.recv_timeout(WATCH_DEBOUNCE).ok();
+        }
+
+        let (updated_count, duration) = measure!(update_graph(
+            &mut graph,
+            args,
+            &tsconfig,
+            &call_patterns,
+            output,
+            changed_paths,
+            created_directories,
+        ));
+        if updated_count == 0 {
+            continue;
+        }
+        print_timer!("Updated {} files in {:?}", updated_count, duration);
+
+        for (query, results) in queries.iter().zip(query_results.iter_mut()) {
+            let new_results = query.get_results(&graph);
+            if new_results != *results {
+                query.print(&graph, output);
+                *results = new_results;
+            }
+        }
+    }
+}
+
+/// Applies a batch of changed paths to the graph, and returns the number of files which were updated
+fn update_graph(
+    graph: &mut DependencyGraph,
+    args: &CliArgs,
+    tsconfig: &TSConfig,
+    call_patterns: &[CallPattern],
+    output: &Output,
+    mut changed_paths: BTreeSet<PathBuf>,
+    created_directories: BTreeSet<PathBuf>,
+) -> usize {
+    // a directory which is created or moved into the search paths only has an event for the directory itself
+    for directory in created_directories {
+        if is_in_search_paths(&args.search_paths, &directory) {
+            changed_paths.extend(get_files(&vec![directory], output));
+        }
+    }
+
+    let is_file_module = |graph: &DependencyGraph, path: &Path| {
+        return graph
+            .get_module_id_for_path(path)
+            .is_some_and(|module_id| graph.get_path_for_module_id(module_id) == path);
+    };
+    let mut changed_files = vec![];
+    let mut removed_files = vec![];
+    for path in changed_paths {
+        if path.is_file() {
+            if is_file_module(graph, &path) || is_in_search_paths(&args.search_paths, &path) {
+                changed_files.push(path);
+            }
+        } else if !path.exists() {
+            // likewise a directory which is deleted or moved out of the search paths only has an event for itself
+            removed_files.extend(
+                graph
+                    .get_module_ids()
+                    .filter(|module_id| graph.get_module_kind(*module_id) == ModuleKind::File)
+                    .map(|module_id| graph.get_path_for_module_id(module_id))
+                    .filter(|file| file.starts_with(&path)),
+            );
+        }
+    }
+
+    let mut parsed_files = Vec::with_capacity(changed_files.len());
+    changed_files
+        .par_iter()
+        .map(|file| {
+            let (parsed_module, _) = parse_file_with_cache(file, call_patterns, None, output);
+            return (file, parsed_module);
+        })
+        .collect_into_vec(&mut parsed_files);
+
+    // a file which is resolved more than once only keeps the errors from its last resolution
+    let mut resolution_errors = BTreeMap::new();
+    for file in removed_files.iter() {
+        resolution_errors.extend(
+            graph
+                .remove_file(file, tsconfig)
+                .expect("Unable to update the graph"),
+        );
+    }
+    for (file, parsed_module) in parsed_files {
+        resolution_errors.extend(
+            graph
+                .update_file(file, parsed_module, tsconfig)
+                .expect("Unable to update the graph"),
+        );
+    }
+    for (file, errors) in resolution_errors.iter() {
+        if !errors.is_empty() {
+            output.report_errors(file, errors);
+        }
+    }
+
+    return removed_files.len() + changed_files.len();
+}
+
+fn read_line() -> Option<String> {
     let mut line = String::new();
     io::stdin().read_line(&mut line).expect("Valid input");
     let line = line.trim();
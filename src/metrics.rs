@@ -0,0 +1,194 @@
+use globset::GlobSet;
+use petgraph::{algo::tarjan_scc, visit::EdgeRef, Direction};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{collections::HashSet, path::PathBuf};
+
+use crate::{
+    breadth_first_expansion::BreadthFirstExpansion,
+    dependency_graph::DependencyGraph,
+    file_system::get_file_size,
+    module::{ModuleGraph, ModuleKind},
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleMetrics {
+    pub path: PathBuf,
+    /// The number of modules which directly import this module
+    pub fan_in: usize,
+    /// The number of modules this module directly imports
+    pub fan_out: usize,
+    pub transitive_dependencies: usize,
+    pub transitive_dependents: usize,
+    /// Martin's instability - `fan_out / (fan_in + fan_out)`. 0 is maximally stable and 1 is maximally unstable.
+    pub instability: f64,
+    /// The minimum number of imports between an entry point and this module, or `None` if it is unreachable
+    pub depth: Option<u32>,
+    /// The size of this file plus every file it transitively depends on
+    pub transitive_bytes: u64,
+}
+
+/// The strongly connected components of the graph as a DAG.
+/// Every module in a component can reach every other module in it, so they all share the same transitive dependencies
+/// and we only need to traverse once per component rather than once per module.
+struct Condensation {
+    component_for_node: Vec<usize>,
+    component_sizes: Vec<usize>,
+    component_bytes: Vec<u64>,
+    outgoing: Vec<Vec<usize>>,
+    incoming: Vec<Vec<usize>>,
+}
+impl Condensation {
+    fn new(module_graph: &ModuleGraph, node_bytes: &[u64]) -> Self {
+        let components = tarjan_scc(module_graph);
+        let mut component_for_node = vec![0; module_graph.node_count()];
+        let mut component_bytes = vec![0; components.len()];
+        for (component_idx, component) in components.iter().enumerate() {
+            for node_idx in component {
+                component_for_node[node_idx.index()] = component_idx;
+                component_bytes[component_idx] += node_bytes[node_idx.index()];
+            }
+        }
+
+        let mut edges = HashSet::new();
+        for edge in module_graph.edge_references() {
+            let from = component_for_node[edge.source().index()];
+            let to = component_for_node[edge.target().index()];
+            if from != to {
+                edges.insert((from, to));
+            }
+        }
+        let mut outgoing = vec![vec![]; components.len()];
+        let mut incoming = vec![vec![]; components.len()];
+        for (from, to) in edges {
+            outgoing[from].push(to);
+            incoming[to].push(from);
+        }
+
+        return Self {
+            component_for_node,
+            component_sizes: components.iter().map(|component| component.len()).collect(),
+            component_bytes,
+            outgoing,
+            incoming,
+        };
+    }
+
+    /// Counts the modules and bytes in every component reachable from each component, including itself
+    fn get_reachable_totals(&self, direction: Direction) -> Vec<(usize, u64)> {
+        let adjacency = match direction {
+            Direction::Outgoing => &self.outgoing,
+            Direction::Incoming => &self.incoming,
+        };
+        let component_count = self.component_sizes.len();
+
+        return (0..component_count)
+            .into_par_iter()
+            .map_init(
+                || vec![false; component_count],
+                |seen, start| {
+                    let mut visited = vec![start];
+                    let mut stack = vec![start];
+                    seen[start] = true;
+                    while let Some(component_idx) = stack.pop() {
+                        for neighbor in adjacency[component_idx].iter() {
+                            if !seen[*neighbor] {
+                                seen[*neighbor] = true;
+                                visited.push(*neighbor);
+                                stack.push(*neighbor);
+                            }
+                        }
+                    }
+
+                    let mut totals = (0, 0);
+                    for component_idx in visited {
+                        // reset the buffer so it can be reused for the next component on this thread
+                        seen[component_idx] = false;
+                        totals.0 += self.component_sizes[component_idx];
+                        totals.1 += self.component_bytes[component_idx];
+                    }
+                    return totals;
+                },
+            )
+            .collect();
+    }
+}
+
+/// Computes the metrics for every file in the graph
+pub fn get_metrics<'a>(
+    graph: &'a DependencyGraph,
+    entry_points: &GlobSet,
+) -> Result<Vec<ModuleMetrics>, &'a str> {
+    let module_graph = graph.get_graph()?;
+
+    let node_paths = module_graph
+        .node_indices()
+        .map(|node_idx| graph.get_path_for_module_id(module_graph[node_idx]))
+        .collect::<Vec<_>>();
+    let node_kinds = module_graph
+        .node_indices()
+        .map(|node_idx| graph.get_module_kind(module_graph[node_idx]))
+        .collect::<Vec<_>>();
+    let node_bytes = node_paths
+        .par_iter()
+        .zip(node_kinds.par_iter())
+        .map(|(path, kind)| {
+            return match kind {
+                ModuleKind::File => get_file_size(path),
+                ModuleKind::NodeModule => 0,
+            };
+        })
+        .collect::<Vec<_>>();
+
+    let condensation = Condensation::new(module_graph, &node_bytes);
+    let dependency_totals = condensation.get_reachable_totals(Direction::Outgoing);
+    let dependent_totals = condensation.get_reachable_totals(Direction::Incoming);
+
+    let mut depths = vec![None; module_graph.node_count()];
+    let entry_nodes = module_graph
+        .node_indices()
+        .filter(|node_idx| node_kinds[node_idx.index()] == ModuleKind::File)
+        .filter(|node_idx| entry_points.is_match(&node_paths[node_idx.index()]))
+        .collect();
+    for visited_node in
+        BreadthFirstExpansion::new(module_graph, Direction::Outgoing, 0, entry_nodes)
+    {
+        depths[visited_node.node_idx.index()] = Some(visited_node.depth);
+    }
+
+    return Ok(module_graph
+        .node_indices()
+        .filter(|node_idx| node_kinds[node_idx.index()] == ModuleKind::File)
+        .map(|node_idx| {
+            let count_distinct = |direction: Direction| -> usize {
+                return module_graph
+                    .neighbors_directed(node_idx, direction)
+                    .filter(|neighbor| *neighbor != node_idx)
+                    .collect::<HashSet<_>>()
+                    .len();
+            };
+            let fan_in = count_distinct(Direction::Incoming);
+            let fan_out = count_distinct(Direction::Outgoing);
+            let component_idx = condensation.component_for_node[node_idx.index()];
+            let (dependency_count, dependency_bytes) = dependency_totals[component_idx];
+            let (dependent_count, _) = dependent_totals[component_idx];
+
+            return ModuleMetrics {
+                path: node_paths[node_idx.index()].clone(),
+                fan_in,
+                fan_out,
+                // the totals include the module itself
+                transitive_dependencies: dependency_count - 1,
+                transitive_dependents: dependent_count - 1,
+                instability: if fan_in + fan_out == 0 {
+                    0.0
+                } else {
+                    fan_out as f64 / (fan_in + fan_out) as f64
+                },
+                depth: depths[node_idx.index()],
+                transitive_bytes: dependency_bytes,
+            };
+        })
+        .collect());
+}
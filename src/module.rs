@@ -4,11 +4,12 @@ use std::{
 };
 
 use petgraph::prelude::DiGraph;
+use serde::{Deserialize, Serialize};
 
 macro_rules! id_impl {
     ($name:ident) => {
         /// u32 allows up to 4,294,967,295 entities with just 4 bytes of storage - which is more than enough forever
-        #[derive(Clone, Copy, Eq, PartialEq)]
+        #[derive(Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
         pub struct $name(u32);
         // Allows easy construction from a usize with `$name::from(usize)`
         impl From<usize> for $name {
@@ -48,7 +49,7 @@ id_impl!(PathId);
 /// Both IDs are a u32 so combined this struct is just 8 bytes - which is the same size as a 64-bit pointer!
 /// This means that copying a Module should be the same speed and size as passing a pointer - which saves us having to
 /// worry as much about trying to deduplicate references and ownership - we just default to copying everything
-#[derive(Clone, Copy, Eq)]
+#[derive(Clone, Copy, Eq, Serialize, Deserialize)]
 pub struct Module {
     pub path_id: PathId,
     pub module_id: ModuleId,
@@ -64,6 +65,32 @@ impl Hash for Module {
     }
 }
 
-// we don't actually use the edge weight in the graph for anything - so an empty struct saves us from allocating any memory
-pub struct EdgeWeight;
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModuleKind {
+    /// A source file discovered in the search paths
+    File,
+    /// A third-party package - eg `react` or `@scope/pkg`
+    NodeModule,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencyKind {
+    /// A plain string import - eg `import 'foo'` or `require('foo')`
+    Static,
+    /// A template literal or string concatenation import which was resolved by matching a glob against the discovered
+    /// files - eg `import(`./locales/${lang}`)`
+    WildcardDynamic,
+}
+
+// the edge weight is kept as small as possible because there is one for every import in the codebase
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct EdgeWeight {
+    pub kind: DependencyKind,
+    /// The index of the dependency in the importing module's `ParsedModule::dependencies`
+    pub dependency_index: u32,
+    /// Type-only imports are erased at runtime, so they never cause runtime issues like import cycles
+    pub is_type_only: bool,
+}
 pub type ModuleGraph = DiGraph<ModuleId, EdgeWeight>;
@@ -0,0 +1,362 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diagnostic::{Diagnostic, DiagnosticRule},
+    metrics::ModuleMetrics,
+    module::ModuleKind,
+    sarif::write_sarif,
+};
+
+// bump this whenever a field is removed or changes meaning - adding fields or record types is not a breaking change
+pub const OUTPUT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable output - the format may change between versions
+    Text,
+    /// A single JSON document
+    Json,
+    /// One JSON document per line, so results can be streamed
+    Ndjson,
+    /// A SARIF 2.1.0 log of the errors and findings, for code scanning tools
+    Sarif,
+}
+
+/// A problem found while building the graph or running a command
+#[derive(Serialize)]
+pub struct OutputError {
+    /// The file the error is about, if any
+    pub path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<DiagnosticRule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputImportHop {
+    pub from: PathBuf,
+    pub line: u32,
+    pub column: u32,
+    pub specifier: PathBuf,
+}
+
+/// A single result of a command - each command only produces a few of the record types
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum OutputRecord {
+    /// A module reached by a dependency query
+    #[serde(rename_all = "camelCase")]
+    Module {
+        path: PathBuf,
+        kind: ModuleKind,
+        depth: u32,
+        /// The module this module was first reached from - `None` for the roots
+        parent: Option<PathBuf>,
+        /// The roots which reach this module, with `--attribute-roots`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        roots: Option<Vec<PathBuf>>,
+        /// The number of imports between the parent and this module, for collapsed graphs
+        #[serde(skip_serializing_if = "Option::is_none")]
+        import_count: Option<usize>,
+    },
+    /// A module which imports the export given by `--export-name`
+    #[serde(rename_all = "camelCase")]
+    Importer {
+        path: PathBuf,
+        line: u32,
+        column: u32,
+    },
+    #[serde(rename_all = "camelCase")]
+    UnusedExport {
+        path: PathBuf,
+        line: u32,
+        column: u32,
+        name: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    DeadFile {
+        path: PathBuf,
+    },
+    #[serde(rename_all = "camelCase")]
+    Cycle {
+        modules: Vec<PathBuf>,
+        shortest_loop: Vec<PathBuf>,
+    },
+    #[serde(rename_all = "camelCase")]
+    AffectedFile {
+        path: PathBuf,
+        is_test: bool,
+    },
+    /// A changed file which is not imported by any module
+    #[serde(rename_all = "camelCase")]
+    UnmatchedChange {
+        path: PathBuf,
+    },
+    /// A changed file which is not reached by any test
+    #[serde(rename_all = "camelCase")]
+    UncoveredChange {
+        path: PathBuf,
+    },
+    #[serde(rename_all = "camelCase")]
+    Test {
+        path: PathBuf,
+    },
+    #[serde(rename_all = "camelCase")]
+    AddedImport {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    #[serde(rename_all = "camelCase")]
+    RemovedImport {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    #[serde(rename_all = "camelCase")]
+    NewCycle {
+        modules: Vec<PathBuf>,
+        shortest_loop: Vec<PathBuf>,
+    },
+    #[serde(rename_all = "camelCase")]
+    NewPackage {
+        name: PathBuf,
+    },
+    #[serde(rename_all = "camelCase")]
+    DependencyGrowth {
+        path: PathBuf,
+        base_count: usize,
+        head_count: usize,
+    },
+    #[serde(rename_all = "camelCase")]
+    RuleViolation {
+        path: PathBuf,
+        line: u32,
+        column: u32,
+        rule: String,
+        specifier: PathBuf,
+        message: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    DeepImport {
+        path: PathBuf,
+        line: u32,
+        column: u32,
+        specifier: PathBuf,
+        package: String,
+        suggestion: Option<String>,
+    },
+    /// A package which is imported but not declared in the package.json
+    #[serde(rename_all = "camelCase")]
+    MissingDependency {
+        package_json: PathBuf,
+        name: String,
+        path: PathBuf,
+        line: u32,
+        column: u32,
+        import_count: usize,
+    },
+    #[serde(rename_all = "camelCase")]
+    UnusedDependency {
+        package_json: PathBuf,
+        name: String,
+    },
+    /// A dev dependency which is imported at runtime by the package's entry points
+    #[serde(rename_all = "camelCase")]
+    MisplacedDependency {
+        package_json: PathBuf,
+        name: String,
+        path: PathBuf,
+        line: u32,
+        column: u32,
+        import_count: usize,
+    },
+    Metrics(ModuleMetrics),
+    #[serde(rename_all = "camelCase")]
+    ImportPath {
+        hops: Vec<OutputImportHop>,
+        to: PathBuf,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonOutput<'a, Q: Serialize> {
+    version: u32,
+    command: &'a str,
+    query: &'a Q,
+    roots: Vec<PathBuf>,
+    results: &'a [OutputRecord],
+    errors: &'a [OutputError],
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum NdjsonLine<'a, Q: Serialize> {
+    #[serde(rename_all = "camelCase")]
+    Query {
+        version: u32,
+        command: &'a str,
+        query: &'a Q,
+        roots: Vec<PathBuf>,
+    },
+    Error(&'a OutputError),
+}
+
+/// Writes the results of commands in the requested format.
+/// Errors are printed to stderr as they are reported in text mode, and are otherwise held back so that they can be
+/// included in the machine-readable output.
+pub struct Output {
+    pub format: OutputFormat,
+    relative_to: Option<PathBuf>,
+    errors: Mutex<Vec<OutputError>>,
+}
+impl Output {
+    /// Paths are written relative to `relative_to` if given, otherwise they are absolute
+    pub fn new(format: OutputFormat, relative_to: Option<PathBuf>) -> Self {
+        return Self {
+            format,
+            relative_to,
+            errors: Mutex::new(vec![]),
+        };
+    }
+
+    pub fn is_text(&self) -> bool {
+        return self.format == OutputFormat::Text;
+    }
+
+    /// Converts a path to the requested style - paths outside of the root, and node module names, are unchanged
+    pub fn path(&self, path: &Path) -> PathBuf {
+        return match &self.relative_to {
+            Some(root) => path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+            None => path.to_path_buf(),
+        };
+    }
+
+    pub fn paths(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        return paths.iter().map(|path| self.path(path)).collect();
+    }
+
+    /// Reports the errors for a file
+    pub fn report_errors(&self, path: &Path, errors: &[Diagnostic]) {
+        if errors.is_empty() {
+            return;
+        }
+        if self.is_text() {
+            eprintln!("❌ Errors for file {}:", path.display());
+            for error in errors {
+                eprintln!(
+                    "❗️ {}:{} {}",
+                    error.location.line, error.location.column, error.message
+                );
+            }
+            eprintln!();
+            return;
+        }
+
+        let path = self.path(path);
+        self.errors
+            .lock()
+            .extend(errors.iter().map(|error| OutputError {
+                path: Some(path.clone()),
+                rule: Some(error.rule),
+                line: Some(error.location.line),
+                column: Some(error.location.column),
+                message: error.message.clone(),
+            }));
+    }
+
+    /// Reports a warning from a command which is not about a specific file
+    pub fn report_warning(&self, message: String) {
+        if self.is_text() {
+            eprintln!("❗️ {}", message);
+            return;
+        }
+
+        self.errors.lock().push(OutputError {
+            path: None,
+            rule: None,
+            line: None,
+            column: None,
+            message,
+        });
+    }
+
+    /// Writes the results of a command in a machine-readable format - text output is written by each command.
+    /// The errors reported since the last write are included.
+    pub fn write<Q: Serialize>(
+        &self,
+        command: &str,
+        query: &Q,
+        roots: &[PathBuf],
+        results: &[OutputRecord],
+    ) {
+        let mut errors = std::mem::take(&mut *self.errors.lock());
+        // files are parsed in parallel, so the errors are sorted to keep the output stable between runs
+        errors.sort_by(|a, b| {
+            return a
+                .path
+                .cmp(&b.path)
+                .then_with(|| a.line.cmp(&b.line))
+                .then_with(|| a.column.cmp(&b.column))
+                .then_with(|| a.message.cmp(&b.message));
+        });
+        let roots = self.paths(roots);
+        let mut stdout = io::stdout().lock();
+        let result = match self.format {
+            OutputFormat::Text => panic!("Text output is written by each command"),
+            OutputFormat::Json => serde_json::to_writer_pretty(
+                &mut stdout,
+                &JsonOutput {
+                    version: OUTPUT_SCHEMA_VERSION,
+                    command,
+                    query,
+                    roots,
+                    results,
+                    errors: &errors,
+                },
+            )
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(stdout)),
+            OutputFormat::Ndjson => write_json_line(
+                &mut stdout,
+                &NdjsonLine::Query {
+                    version: OUTPUT_SCHEMA_VERSION,
+                    command,
+                    query,
+                    roots,
+                },
+            )
+            .and_then(|_| {
+                results
+                    .iter()
+                    .try_for_each(|record| write_json_line(&mut stdout, record))
+            })
+            .and_then(|_| {
+                errors.iter().try_for_each(|error| {
+                    write_json_line(&mut stdout, &NdjsonLine::<Q>::Error(error))
+                })
+            }),
+            OutputFormat::Sarif => write_sarif(&mut stdout, results, &errors),
+        };
+        match result {
+            Ok(_) => {}
+            // the reader has gone away - eg `athena ... | head` - so there is no one left to write to
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+            Err(e) => panic!("Unable to write output: {}", e),
+        }
+    }
+}
+
+fn write_json_line(writer: &mut impl Write, value: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    return writeln!(writer);
+}
@@ -0,0 +1,334 @@
+use petgraph::visit::{Dfs, EdgeFiltered, EdgeRef};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    import_visitor::SourceLocation,
+    module::{ModuleId, ModuleKind},
+    package_json::resolve_entry_point,
+};
+
+// https://nodejs.org/api/modules.html#built-in-modules
+const NODE_BUILTIN_MODULES: [&str; 43] = [
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "sys",
+    "test",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+pub struct DependencyUsage {
+    pub name: String,
+    /// The first import of the package
+    pub path: PathBuf,
+    pub location: SourceLocation,
+    pub import_count: usize,
+}
+
+pub struct PackageDependencyIssues {
+    pub package_json: PathBuf,
+    /// Packages which are imported but not declared in any of the dependency fields
+    pub missing: Vec<DependencyUsage>,
+    /// Packages declared in `dependencies` which are never imported
+    pub unused: Vec<String>,
+    /// Packages declared only in `devDependencies` which are imported at runtime by files reachable from the package's
+    /// entry points
+    pub misplaced: Vec<DependencyUsage>,
+}
+
+struct PackageImport {
+    name: String,
+    from_module_id: ModuleId,
+    location: SourceLocation,
+    is_type_only: bool,
+}
+
+/// The name of the DefinitelyTyped package which provides the types for a package - eg `@types/scope__name`
+fn get_types_package_name(name: &str) -> String {
+    return format!(
+        "@types/{}",
+        name.strip_prefix('@').unwrap_or(name).replace('/', "__")
+    );
+}
+
+fn is_node_builtin_module(name: &str) -> bool {
+    return name.starts_with("node:") || NODE_BUILTIN_MODULES.contains(&name);
+}
+
+/// Cross-references the packages imported by each package's files with the dependencies its package.json declares.
+/// Type-only imports are erased at runtime, so they may be satisfied by an `@types/*` package and are never misplaced.
+pub fn get_package_dependency_issues(
+    graph: &DependencyGraph,
+) -> Result<Vec<PackageDependencyIssues>, &str> {
+    let module_graph = graph.get_graph()?;
+    let package_jsons = graph.get_package_jsons();
+
+    let mut package_imports: Vec<Vec<PackageImport>> =
+        package_jsons.iter().map(|_| vec![]).collect();
+    for edge in module_graph.edge_references() {
+        let from_module_id = module_graph[edge.source()];
+        let to_module_id = module_graph[edge.target()];
+        let Some(from_package) = graph.get_package_json_index_for_module_id(from_module_id) else {
+            continue;
+        };
+
+        let name = match graph.get_module_kind(to_module_id) {
+            ModuleKind::NodeModule => graph
+                .get_path_for_module_id(to_module_id)
+                .to_string_lossy()
+                .to_string(),
+            ModuleKind::File => {
+                // imports of other packages within the search paths need to be declared too
+                match graph.get_package_json_index_for_module_id(to_module_id) {
+                    Some(to_package) if to_package != from_package => {
+                        match &package_jsons[to_package].name {
+                            Some(name) => name.clone(),
+                            None => continue,
+                        }
+                    }
+                    _ => continue,
+                }
+            }
+        };
+        if is_node_builtin_module(&name) {
+            continue;
+        }
+        let Some(dependency) = graph.get_dependency_for_edge(from_module_id, edge.weight()) else {
+            continue;
+        };
+
+        package_imports[from_package].push(PackageImport {
+            name,
+            from_module_id,
+            location: dependency.location,
+            is_type_only: edge.weight().is_type_only,
+        });
+    }
+
+    let to_usages = |imports: Vec<&PackageImport>| -> Vec<DependencyUsage> {
+        let mut imports_by_name: HashMap<&str, Vec<(PathBuf, SourceLocation)>> = HashMap::new();
+        for import in imports {
+            imports_by_name.entry(&import.name).or_default().push((
+                graph.get_path_for_module_id(import.from_module_id),
+                import.location,
+            ));
+        }
+
+        let mut usages = imports_by_name
+            .into_iter()
+            .map(|(name, mut imports)| {
+                imports.sort_by(|(a_path, a_location), (b_path, b_location)| {
+                    a_path
+                        .cmp(b_path)
+                        .then_with(|| a_location.line.cmp(&b_location.line))
+                        .then_with(|| a_location.column.cmp(&b_location.column))
+                });
+                let (path, location) = imports[0].clone();
+                return DependencyUsage {
+                    name: name.to_owned(),
+                    path,
+                    location,
+                    import_count: imports.len(),
+                };
+            })
+            .collect::<Vec<_>>();
+        usages.sort_by(|a, b| a.name.cmp(&b.name));
+        return usages;
+    };
+
+    // type-only edges are erased at runtime, so they can't make a dev dependency reachable in production
+    let runtime_graph = EdgeFiltered::from_fn(module_graph, |edge| !edge.weight().is_type_only);
+
+    let mut issues = vec![];
+    for (package_idx, package_json) in package_jsons.iter().enumerate() {
+        let imports = &package_imports[package_idx];
+        let is_declared = |name: &str| -> bool {
+            return package_json.dependencies.contains(name)
+                || package_json.dev_dependencies.contains(name)
+                || package_json.other_dependencies.contains(name);
+        };
+
+        let missing = to_usages(
+            imports
+                .iter()
+                .filter(|import| {
+                    let is_satisfied = is_declared(&import.name)
+                        || (import.is_type_only
+                            && is_declared(&get_types_package_name(&import.name)));
+                    return !is_satisfied;
+                })
+                .collect(),
+        );
+
+        let used = imports
+            .iter()
+            .flat_map(|import| [import.name.clone(), get_types_package_name(&import.name)])
+            .collect::<HashSet<_>>();
+        let mut unused = package_json
+            .dependencies
+            .iter()
+            .filter(|name| !used.contains(*name))
+            .cloned()
+            .collect::<Vec<_>>();
+        unused.sort();
+
+        let mut production_modules = HashSet::new();
+        let entry_points = package_json
+            .entry_points
+            .iter()
+            .flat_map(|entry_point| resolve_entry_point(graph, entry_point))
+            .collect::<Vec<_>>();
+        if let Some(first_entry_point) = entry_points.first() {
+            let mut dfs = Dfs::new(
+                &runtime_graph,
+                graph.get_node_for_module_id(*first_entry_point),
+            );
+            for entry_point in entry_points.iter() {
+                dfs.move_to(graph.get_node_for_module_id(*entry_point));
+                while let Some(node_idx) = dfs.next(&runtime_graph) {
+                    production_modules.insert(module_graph[node_idx]);
+                }
+            }
+        }
+        let misplaced = to_usages(
+            imports
+                .iter()
+                .filter(|import| {
+                    return !import.is_type_only
+                        && production_modules.contains(&import.from_module_id)
+                        && package_json.dev_dependencies.contains(&import.name)
+                        && !package_json.dependencies.contains(&import.name)
+                        && !package_json.other_dependencies.contains(&import.name);
+                })
+                .collect(),
+        );
+
+        if !missing.is_empty() || !unused.is_empty() || !misplaced.is_empty() {
+            issues.push(PackageDependencyIssues {
+                package_json: package_json.path.clone(),
+                missing,
+                unused,
+                misplaced,
+            });
+        }
+    }
+
+    return Ok(issues);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestProject;
+
+    #[test]
+    fn scoped_packages_are_matched_by_their_full_name() {
+        let project = TestProject::new(&[
+            (
+                "package.json",
+                r#"{ "name": "app", "dependencies": { "@babel/core": "1", "@babel/parser": "1" } }"#,
+            ),
+            (
+                "index.ts",
+                "import { transform } from '@babel/core';\nimport '@babel/core/lib/config';\nimport { x } from '@other/pkg';",
+            ),
+        ]);
+        let graph = project.build_graph();
+
+        let issues = get_package_dependency_issues(&graph).unwrap();
+        assert_eq!(issues.len(), 1);
+        let missing = issues[0]
+            .missing
+            .iter()
+            .map(|usage| (usage.name.as_str(), usage.import_count))
+            .collect::<Vec<_>>();
+        assert_eq!(missing, vec![("@other/pkg", 1)]);
+        assert_eq!(issues[0].unused, vec!["@babel/parser".to_string()]);
+    }
+
+    #[test]
+    fn dependencies_are_checked_against_their_usage() {
+        let project = TestProject::new(&[
+            (
+                "package.json",
+                r#"{
+                    "name": "app",
+                    "main": "./index.js",
+                    "dependencies": { "used": "1", "unused": "1" },
+                    "devDependencies": { "dev-only": "1", "test-lib": "1", "@types/typed": "1" }
+                }"#,
+            ),
+            (
+                "index.ts",
+                "import 'used';\nimport 'dev-only';\nimport type { T } from 'typed';\nimport fs from 'fs';\nimport path from 'node:path';",
+            ),
+            (
+                "index.test.ts",
+                "import './index';\nimport 'test-lib';\nimport 'undeclared';\nimport 'undeclared/deep';",
+            ),
+        ]);
+        let graph = project.build_graph();
+
+        let issues = get_package_dependency_issues(&graph).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].package_json, project.path("package.json"));
+        let missing = issues[0]
+            .missing
+            .iter()
+            .map(|usage| (usage.name.as_str(), usage.path.clone(), usage.import_count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            missing,
+            vec![("undeclared", project.path("index.test.ts"), 2)]
+        );
+        assert_eq!(issues[0].unused, vec!["unused".to_string()]);
+        let misplaced = issues[0]
+            .misplaced
+            .iter()
+            .map(|usage| usage.name.as_str())
+            .collect::<Vec<_>>();
+        // test-lib is only imported by a file which isn't reachable from the entry point
+        assert_eq!(misplaced, vec!["dev-only"]);
+    }
+}
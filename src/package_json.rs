@@ -0,0 +1,255 @@
+use clean_path::Clean;
+use globset::GlobBuilder;
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    module::{ModuleId, ModuleKind},
+    output::Output,
+};
+
+const PACKAGE_JSON_FILE_NAME: &str = "package.json";
+
+// This obviously isn't the entire package.json spec - we only declare the subsets we actually care about
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PackageJsonBin {
+    Single(String),
+    Named(serde_json::Map<String, serde_json::Value>),
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageJsonRaw {
+    name: Option<String>,
+    main: Option<String>,
+    module: Option<String>,
+    bin: Option<PackageJsonBin>,
+    exports: Option<serde_json::Value>,
+    #[serde(default)]
+    dependencies: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    dev_dependencies: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    peer_dependencies: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    optional_dependencies: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PackageJson {
+    pub path: PathBuf,
+    pub name: Option<String>,
+    /// The absolute paths of every file declared as an entry point via `main`, `module`, `bin` or `exports`.
+    /// Subpath patterns from `exports` contain a `*` wildcard.
+    pub entry_points: Vec<PathBuf>,
+    /// The subpaths other packages may import and the absolute paths of the files they point to - eg
+    /// `("./button", "/repo/ui/src/button.ts")`. These come from `exports`, or from `main` and `module` (or the
+    /// implicit `index`) if there is no exports map.
+    pub public_subpaths: Vec<(String, PathBuf)>,
+    /// The names of the packages declared in `dependencies`
+    pub dependencies: HashSet<String>,
+    /// The names of the packages declared in `devDependencies`
+    pub dev_dependencies: HashSet<String>,
+    /// The names of the packages declared in `peerDependencies` or `optionalDependencies`
+    pub other_dependencies: HashSet<String>,
+}
+impl PackageJson {
+    pub fn directory(&self) -> &Path {
+        return self.path.parent().expect("Path should not be the root");
+    }
+
+    /// Maps an import subpath (eg `.` or `./button`) to the files it may refer to, following subpath patterns like
+    /// `"./*": "./src/*.ts"`
+    pub fn resolve_public_subpath(&self, subpath: &str) -> Vec<PathBuf> {
+        return self
+            .public_subpaths
+            .iter()
+            .filter_map(|(public_subpath, path)| {
+                if public_subpath == subpath {
+                    return Some(path.clone());
+                }
+
+                let (prefix, suffix) = public_subpath.split_once('*')?;
+                let matched = subpath.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                return Some(PathBuf::from(
+                    path.to_string_lossy().replacen('*', matched, 1),
+                ));
+            })
+            .collect();
+    }
+}
+
+pub fn parse_package_json(path: &Path) -> Result<PackageJson, String> {
+    let raw_json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let package_json_raw: PackageJsonRaw =
+        serde_json::from_str(&raw_json).map_err(|e| e.to_string())?;
+
+    let mut entry_points = vec![];
+    if let Some(main) = &package_json_raw.main {
+        entry_points.push(main.to_owned());
+    }
+    if let Some(module) = &package_json_raw.module {
+        entry_points.push(module.to_owned());
+    }
+    match package_json_raw.bin {
+        Some(PackageJsonBin::Single(bin)) => entry_points.push(bin),
+        Some(PackageJsonBin::Named(bins)) => {
+            collect_json_strings(&serde_json::Value::Object(bins), &mut entry_points)
+        }
+        None => {}
+    }
+    if let Some(exports) = &package_json_raw.exports {
+        // the keys of the exports map are the public subpaths - the values are the files (possibly nested under
+        // conditions like `import` or `require`)
+        collect_json_strings(exports, &mut entry_points);
+    }
+
+    let mut public_subpaths = vec![];
+    match &package_json_raw.exports {
+        Some(exports) => collect_exports_subpaths(exports, &mut public_subpaths),
+        None => {
+            for entry_point in [&package_json_raw.main, &package_json_raw.module]
+                .into_iter()
+                .flatten()
+            {
+                public_subpaths.push((String::from("."), entry_point.to_owned()));
+            }
+            // node falls back to the index file if there is no main
+            public_subpaths.push((String::from("."), String::from("index")));
+        }
+    }
+
+    let directory = path.parent().expect("Path should not be the root");
+    return Ok(PackageJson {
+        path: path.to_owned(),
+        name: package_json_raw.name,
+        entry_points: entry_points
+            .iter()
+            .map(|entry_point| directory.join(entry_point).clean())
+            .collect(),
+        public_subpaths: public_subpaths
+            .into_iter()
+            .map(|(subpath, entry_point)| (subpath, directory.join(entry_point).clean()))
+            .collect(),
+        dependencies: package_json_raw
+            .dependencies
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect(),
+        dev_dependencies: package_json_raw
+            .dev_dependencies
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect(),
+        other_dependencies: package_json_raw
+            .peer_dependencies
+            .into_iter()
+            .chain(package_json_raw.optional_dependencies)
+            .map(|(name, _)| name)
+            .collect(),
+    });
+}
+
+/// Collects the `(subpath, file)` pairs from an exports map, flattening any conditions
+fn collect_exports_subpaths(exports: &serde_json::Value, subpaths: &mut Vec<(String, String)>) {
+    let mut add_subpath = |subpath: &str, value: &serde_json::Value| {
+        let mut files = vec![];
+        collect_json_strings(value, &mut files);
+        for file in files {
+            subpaths.push((subpath.to_owned(), file));
+        }
+    };
+
+    match exports {
+        // subpaths are keyed by a path starting with `.` - otherwise the keys are conditions for the root subpath
+        serde_json::Value::Object(object) if object.keys().all(|key| key.starts_with('.')) => {
+            for (subpath, value) in object {
+                add_subpath(subpath, value);
+            }
+        }
+        _ => add_subpath(".", exports),
+    }
+}
+
+/// Finds every package.json within the search paths, ignoring anything in node_modules.
+/// Files which can't be read or parsed - eg a deliberately broken test fixture - are reported and skipped.
+pub fn find_package_jsons(search_paths: &[PathBuf], output: &Output) -> Vec<PackageJson> {
+    let mut walk_builder = WalkBuilder::new(&search_paths[0]);
+    for path in search_paths.iter().skip(1) {
+        walk_builder.add(path);
+    }
+    walk_builder.filter_entry(|entry| entry.file_name() != "node_modules");
+
+    let mut package_jsons = walk_builder
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name() == PACKAGE_JSON_FILE_NAME)
+        .filter_map(|entry| match parse_package_json(entry.path()) {
+            Ok(package_json) => Some(package_json),
+            Err(e) => {
+                output.report_warning(format!(
+                    "Skipping {} as it is not a valid package.json: {}",
+                    entry.path().display(),
+                    e
+                ));
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    package_jsons.sort_by(|a, b| a.path.cmp(&b.path));
+
+    return package_jsons;
+}
+
+/// Matches a package.json entry point to the file modules it refers to.
+/// Entry points commonly refer to built files (eg `index.js`) so we also match against the extension-less path to
+/// find the source file (eg `index.ts`).
+pub fn resolve_entry_point(graph: &DependencyGraph, entry_point: &Path) -> Vec<ModuleId> {
+    let entry_point_str = entry_point.to_string_lossy();
+    if entry_point_str.contains('*') {
+        // subpath patterns - eg `"./features/*": "./src/features/*.js"`
+        let mut module_ids = vec![];
+        for pattern in [entry_point.to_path_buf(), entry_point.with_extension("")] {
+            if let Ok(glob) = GlobBuilder::new(&pattern.to_string_lossy())
+                .literal_separator(false)
+                .build()
+            {
+                module_ids.extend(graph.get_file_module_ids_matching_glob(&glob.compile_matcher()));
+            }
+        }
+        return module_ids;
+    }
+
+    for path in [entry_point.to_path_buf(), entry_point.with_extension("")] {
+        let path = graph.get_group_for_file(&path).unwrap_or(&path);
+        if let Some(module_id) = graph.get_module_id_for_path(path) {
+            if graph.get_module_kind(module_id) == ModuleKind::File {
+                return vec![module_id];
+            }
+        }
+    }
+
+    return vec![];
+}
+
+fn collect_json_strings(value: &serde_json::Value, strings: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(string) => strings.push(string.to_owned()),
+        serde_json::Value::Array(array) => {
+            for value in array {
+                collect_json_strings(value, strings);
+            }
+        }
+        serde_json::Value::Object(object) => {
+            for value in object.values() {
+                collect_json_strings(value, strings);
+            }
+        }
+        serde_json::Value::Null | serde_json::Value::Bool(_) | serde_json::Value::Number(_) => {}
+    }
+}
@@ -11,8 +11,8 @@ use swc_ecma_visit::{VisitMut, VisitMutWith};
 
 use crate::file_system::{extensions, is_declaration_file};
 
-pub fn parse_file(path: &Path, visitor: &mut dyn VisitMut) {
-    let cm: Lrc<SourceMap> = Default::default();
+/// Parses the file into the given source map, so that the visitor can use the source map to look up span locations
+pub fn parse_file(cm: &Lrc<SourceMap>, path: &Path, visitor: &mut dyn VisitMut) {
     let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(cm.clone()));
 
     let fm = cm
@@ -0,0 +1,122 @@
+use petgraph::{visit::EdgeRef, Direction};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    import_visitor::SourceLocation,
+    module::{ModuleId, ModuleKind},
+    package_json::resolve_entry_point,
+};
+
+pub struct DeepImport {
+    pub path: PathBuf,
+    /// The import string as it was written in the source file
+    pub specifier: PathBuf,
+    pub location: SourceLocation,
+    pub package_name: String,
+    /// A public import of the package which exposes the imported file, if there is one
+    pub suggestion: Option<String>,
+}
+
+/// Finds the imports which cross into another package within the search paths, but which import a file that is not
+/// one of that package's public entry points
+pub fn get_deep_imports(graph: &DependencyGraph) -> Result<Vec<DeepImport>, &str> {
+    let module_graph = graph.get_graph()?;
+    let package_jsons = graph.get_package_jsons();
+
+    // the modules each package exposes, along with the subpath used to import them
+    let public_modules = package_jsons
+        .iter()
+        .map(|package_json| {
+            let mut public_modules: HashMap<ModuleId, &str> = HashMap::new();
+            for (subpath, path) in package_json.public_subpaths.iter() {
+                for module_id in resolve_entry_point(graph, path) {
+                    let existing = public_modules.entry(module_id).or_insert(subpath);
+                    // prefer the shortest subpath, eg `.` over `./index`
+                    if subpath.len() < existing.len() {
+                        *existing = subpath;
+                    }
+                }
+            }
+            return public_modules;
+        })
+        .collect::<Vec<_>>();
+
+    let mut owning_packages: HashMap<ModuleId, Option<usize>> = HashMap::new();
+    let mut reachable_from_public_module: HashMap<ModuleId, HashSet<ModuleId>> = HashMap::new();
+    let mut deep_imports = vec![];
+    for edge in module_graph.edge_references() {
+        let from_module_id = module_graph[edge.source()];
+        let to_module_id = module_graph[edge.target()];
+        if graph.get_module_kind(from_module_id) != ModuleKind::File
+            || graph.get_module_kind(to_module_id) != ModuleKind::File
+        {
+            continue;
+        }
+
+        let from_package = *owning_packages
+            .entry(from_module_id)
+            .or_insert_with(|| graph.get_package_json_index_for_module_id(from_module_id));
+        let to_package = *owning_packages
+            .entry(to_module_id)
+            .or_insert_with(|| graph.get_package_json_index_for_module_id(to_module_id));
+        let Some(to_package) = to_package else {
+            continue;
+        };
+        let Some(package_name) = &package_jsons[to_package].name else {
+            // a package without a name cannot be imported, so it has no public API to enforce
+            continue;
+        };
+        if from_package == Some(to_package)
+            || public_modules[to_package].contains_key(&to_module_id)
+        {
+            continue;
+        }
+
+        let Some(dependency) = graph.get_dependency_for_edge(from_module_id, edge.weight()) else {
+            continue;
+        };
+
+        // suggest the public entry point which the imported file is reachable from, as it is likely re-exported there
+        let mut candidates = public_modules[to_package]
+            .iter()
+            .filter(|(_, subpath)| !subpath.contains('*'))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, subpath)| (subpath.len(), **subpath));
+        let mut suggestion = None;
+        for (public_module_id, subpath) in candidates {
+            if !reachable_from_public_module.contains_key(public_module_id) {
+                reachable_from_public_module.insert(
+                    *public_module_id,
+                    graph.get_reachable_module_ids(&[*public_module_id], Direction::Outgoing)?,
+                );
+            }
+            if reachable_from_public_module[public_module_id].contains(&to_module_id) {
+                suggestion = Some(match subpath.strip_prefix('.') {
+                    Some(rest) => format!("{}{}", package_name, rest),
+                    None => package_name.clone(),
+                });
+                break;
+            }
+        }
+
+        deep_imports.push(DeepImport {
+            path: graph.get_path_for_module_id(from_module_id),
+            specifier: dependency.specifier.clone(),
+            location: dependency.location,
+            package_name: package_name.clone(),
+            suggestion,
+        });
+    }
+    deep_imports.sort_by(|a, b| {
+        a.path
+            .cmp(&b.path)
+            .then_with(|| a.location.line.cmp(&b.location.line))
+            .then_with(|| a.location.column.cmp(&b.location.column))
+    });
+
+    return Ok(deep_imports);
+}
@@ -0,0 +1,186 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use json_comments::StripComments;
+use petgraph::visit::EdgeRef;
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    file_system::build_glob_set_relative_to,
+    import_visitor::SourceLocation,
+    module::{ModuleId, ModuleKind},
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RuleRaw {
+    name: String,
+    message: Option<String>,
+    #[serde(default)]
+    from: Vec<String>,
+    #[serde(default)]
+    except_from: Vec<String>,
+    to: Vec<String>,
+    #[serde(default)]
+    except_to: Vec<String>,
+    #[serde(default)]
+    allow_type_only: bool,
+}
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct RulesConfigRaw {
+    rules: Vec<RuleRaw>,
+}
+
+/// Globs which match either files or node modules.
+/// File globs are relative to the config file, whilst node modules are matched by their package name - eg `lodash` or
+/// `@scope/*`.
+struct ModuleMatcher {
+    files: GlobSet,
+    node_modules: GlobSet,
+}
+impl ModuleMatcher {
+    fn new(globs: &[String], directory: &Path) -> Result<Self, globset::Error> {
+        let mut node_modules = GlobSetBuilder::new();
+        for glob in globs {
+            node_modules.add(Glob::new(glob)?);
+        }
+
+        return Ok(Self {
+            files: build_glob_set_relative_to(globs, directory)?,
+            node_modules: node_modules.build()?,
+        });
+    }
+
+    fn is_match(&self, path: &Path, kind: ModuleKind) -> bool {
+        return match kind {
+            ModuleKind::File => self.files.is_match(path),
+            ModuleKind::NodeModule => self.node_modules.is_match(path),
+        };
+    }
+}
+
+/// Forbids imports from the `from` modules to the `to` modules
+pub struct Rule {
+    pub name: String,
+    /// An explanation of the rule to show alongside each violation
+    pub message: Option<String>,
+    /// `None` means the rule applies to every file
+    from: Option<ModuleMatcher>,
+    except_from: ModuleMatcher,
+    to: ModuleMatcher,
+    except_to: ModuleMatcher,
+    allow_type_only: bool,
+}
+
+pub fn parse_rules_config(path: &Path) -> Vec<Rule> {
+    let raw_json_with_comments = std::fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("Unable to read rules config {}", path.display()));
+    let raw_json = StripComments::new(raw_json_with_comments.as_bytes());
+    let rules_config_raw: RulesConfigRaw = serde_json::from_reader(raw_json)
+        .unwrap_or_else(|e| panic!("Unable to parse rules config {}: {}", path.display(), e));
+
+    let directory = path.parent().expect("Path should not be the root");
+    let build_matcher = |rule: &RuleRaw, globs: &[String]| -> ModuleMatcher {
+        return ModuleMatcher::new(globs, directory)
+            .unwrap_or_else(|e| panic!("Invalid glob in rule \"{}\": {}", rule.name, e));
+    };
+    return rules_config_raw
+        .rules
+        .iter()
+        .map(|rule| Rule {
+            name: rule.name.clone(),
+            message: rule.message.clone(),
+            from: if rule.from.is_empty() {
+                None
+            } else {
+                Some(build_matcher(rule, &rule.from))
+            },
+            except_from: build_matcher(rule, &rule.except_from),
+            to: build_matcher(rule, &rule.to),
+            except_to: build_matcher(rule, &rule.except_to),
+            allow_type_only: rule.allow_type_only,
+        })
+        .collect();
+}
+
+pub struct RuleViolation<'a> {
+    pub rule: &'a Rule,
+    pub path: PathBuf,
+    /// The import string as it was written in the source file
+    pub specifier: PathBuf,
+    pub location: SourceLocation,
+}
+
+/// Checks every import in the graph against the rules, returning the violations sorted by file and location
+pub fn check_rules<'a>(
+    graph: &'a DependencyGraph,
+    rules: &'a [Rule],
+) -> Result<Vec<RuleViolation<'a>>, &'a str> {
+    let module_graph = graph.get_graph()?;
+
+    // there are far fewer modules than imports, so we match each module against the globs once up-front
+    let module_ids = graph.get_module_ids().collect::<Vec<_>>();
+    let module_count = module_ids.len();
+    let (importer_rules, imported_rules): (Vec<Vec<bool>>, Vec<Vec<bool>>) = rules
+        .par_iter()
+        .map(|rule| {
+            let mut is_importer = vec![false; module_count];
+            let mut is_imported = vec![false; module_count];
+            for module_id in module_ids.iter() {
+                let path = graph.get_path_for_module_id(*module_id);
+                let kind = graph.get_module_kind(*module_id);
+                is_importer[usize::from(*module_id)] = kind == ModuleKind::File
+                    && rule
+                        .from
+                        .as_ref()
+                        .is_none_or(|from| from.is_match(&path, kind))
+                    && !rule.except_from.is_match(&path, kind);
+                is_imported[usize::from(*module_id)] =
+                    rule.to.is_match(&path, kind) && !rule.except_to.is_match(&path, kind);
+            }
+            return (is_importer, is_imported);
+        })
+        .unzip();
+
+    let mut violations = module_graph
+        .edge_references()
+        .par_bridge()
+        .flat_map_iter(|edge| {
+            let from_module_id: ModuleId = module_graph[edge.source()];
+            let to_module_id: ModuleId = module_graph[edge.target()];
+            let weight = edge.weight();
+            let importer_rules = &importer_rules;
+            let imported_rules = &imported_rules;
+            return rules
+                .iter()
+                .enumerate()
+                .filter_map(move |(rule_idx, rule)| {
+                    if !importer_rules[rule_idx][usize::from(from_module_id)]
+                        || !imported_rules[rule_idx][usize::from(to_module_id)]
+                        || (rule.allow_type_only && weight.is_type_only)
+                    {
+                        return None;
+                    }
+
+                    let dependency = graph.get_dependency_for_edge(from_module_id, weight)?;
+                    return Some(RuleViolation {
+                        rule,
+                        path: graph.get_path_for_module_id(from_module_id),
+                        specifier: dependency.specifier.clone(),
+                        location: dependency.location,
+                    });
+                });
+        })
+        .collect::<Vec<_>>();
+    violations.sort_by(|a, b| {
+        a.path
+            .cmp(&b.path)
+            .then_with(|| a.location.line.cmp(&b.location.line))
+            .then_with(|| a.location.column.cmp(&b.location.column))
+            .then_with(|| a.rule.name.cmp(&b.rule.name))
+    });
+
+    return Ok(violations);
+}
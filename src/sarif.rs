@@ -0,0 +1,442 @@
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use crate::output::{OutputError, OutputRecord};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+// relative artifact URIs are resolved against this base, which is the directory athena was run from
+const SOURCE_ROOT_BASE_ID: &str = "%SRCROOT%";
+
+#[derive(Serialize)]
+struct SarifMessage {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRegion {
+    start_line: u32,
+    start_column: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<SarifRegion>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    physical_location: SarifPhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<SarifMessage>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRule {
+    id: String,
+    short_description: SarifMessage,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: SarifMessage,
+    locations: Vec<SarifLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<SarifLocation>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifNotification {
+    level: &'static str,
+    message: SarifMessage,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifInvocation {
+    execution_successful: bool,
+    tool_execution_notifications: Vec<SarifNotification>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifDriver {
+    name: &'static str,
+    version: &'static str,
+    rules: Vec<SarifRule>,
+}
+
+#[derive(Serialize)]
+struct SarifTool {
+    driver: SarifDriver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRun {
+    tool: SarifTool,
+    original_uri_base_ids: BTreeMap<&'static str, SarifArtifactLocation>,
+    invocations: Vec<SarifInvocation>,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<SarifRun>,
+}
+
+/// A result of a command which points at a problem in the code, as opposed to a result of a query
+struct Finding<'a> {
+    rule_id: String,
+    description: String,
+    level: &'static str,
+    message: String,
+    path: &'a Path,
+    /// The line and column of the problem, if it is about a specific import or export
+    position: Option<(u32, u32)>,
+    /// Other files involved in the problem, eg the rest of a cycle
+    related: Vec<(&'a Path, String)>,
+}
+
+fn get_cycle_finding(shortest_loop: &[PathBuf]) -> Finding<'_> {
+    return Finding {
+        rule_id: String::from("import-cycle"),
+        description: String::from("The module is part of an import cycle"),
+        level: "warning",
+        message: format!(
+            "Import cycle: {}",
+            shortest_loop
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        ),
+        path: &shortest_loop[0],
+        position: None,
+        related: shortest_loop[1..shortest_loop.len() - 1]
+            .iter()
+            .map(|path| (path.as_path(), String::from("Part of the import cycle")))
+            .collect(),
+    };
+}
+
+/// Converts the records which are findings - query results like modules and metrics have no SARIF equivalent
+fn get_finding(record: &OutputRecord) -> Option<Finding<'_>> {
+    return match record {
+        OutputRecord::UnusedExport {
+            path,
+            line,
+            column,
+            name,
+        } => Some(Finding {
+            rule_id: String::from("unused-export"),
+            description: String::from("The export is not imported by any other module"),
+            level: "warning",
+            message: format!("Export \"{}\" is not imported by any other module", name),
+            path,
+            position: Some((*line, *column)),
+            related: vec![],
+        }),
+        OutputRecord::DeadFile { path } => Some(Finding {
+            rule_id: String::from("dead-file"),
+            description: String::from("The file cannot be reached from any entry point"),
+            level: "warning",
+            message: String::from("File cannot be reached from any entry point"),
+            path,
+            position: None,
+            related: vec![],
+        }),
+        OutputRecord::Cycle { shortest_loop, .. } => Some(get_cycle_finding(shortest_loop)),
+        OutputRecord::NewCycle { shortest_loop, .. } => Some(get_cycle_finding(shortest_loop)),
+        OutputRecord::RuleViolation {
+            path,
+            line,
+            column,
+            rule,
+            specifier,
+            message,
+        } => Some(Finding {
+            rule_id: format!("rules/{}", rule),
+            description: format!("Imports disallowed by the \"{}\" rule", rule),
+            level: "error",
+            message: format!(
+                "Import of \"{}\" is not allowed{}",
+                specifier.display(),
+                match message {
+                    Some(message) => format!(" - {}", message),
+                    None => String::new(),
+                }
+            ),
+            path,
+            position: Some((*line, *column)),
+            related: vec![],
+        }),
+        OutputRecord::DeepImport {
+            path,
+            line,
+            column,
+            specifier,
+            package,
+            suggestion,
+        } => Some(Finding {
+            rule_id: String::from("deep-import"),
+            description: String::from(
+                "The import reaches into a package instead of using its public API",
+            ),
+            level: "error",
+            message: format!(
+                "Import of \"{}\" is not part of the public API of {}{}",
+                specifier.display(),
+                package,
+                match suggestion {
+                    Some(suggestion) => format!(" - import from \"{}\" instead", suggestion),
+                    None => String::new(),
+                }
+            ),
+            path,
+            position: Some((*line, *column)),
+            related: vec![],
+        }),
+        OutputRecord::MissingDependency {
+            package_json,
+            name,
+            path,
+            line,
+            column,
+            ..
+        } => Some(Finding {
+            rule_id: String::from("missing-dependency"),
+            description: String::from(
+                "The package is imported but not declared in the package.json",
+            ),
+            level: "error",
+            message: format!("\"{}\" is not declared in {}", name, package_json.display()),
+            path,
+            position: Some((*line, *column)),
+            related: vec![(package_json, String::from("The package.json"))],
+        }),
+        OutputRecord::UnusedDependency { package_json, name } => Some(Finding {
+            rule_id: String::from("unused-dependency"),
+            description: String::from("The dependency is declared but never imported"),
+            level: "warning",
+            message: format!("\"{}\" is declared but never imported", name),
+            path: package_json,
+            position: None,
+            related: vec![],
+        }),
+        OutputRecord::MisplacedDependency {
+            package_json,
+            name,
+            path,
+            line,
+            column,
+            ..
+        } => Some(Finding {
+            rule_id: String::from("misplaced-dependency"),
+            description: String::from(
+                "The dev dependency is imported at runtime by the package's entry points",
+            ),
+            level: "warning",
+            message: format!(
+                "\"{}\" is a dev dependency in {} but is imported at runtime",
+                name,
+                package_json.display()
+            ),
+            path,
+            position: Some((*line, *column)),
+            related: vec![(package_json, String::from("The package.json"))],
+        }),
+        _ => None,
+    };
+}
+
+/// Percent-encodes a path for use in a URI
+fn encode_uri_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    return encoded;
+}
+
+fn get_uri_path(path: &Path) -> String {
+    let components = path
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    return encode_uri_path(&components.join("/"));
+}
+
+/// Code scanning tools expect paths relative to the repository, so paths within the current directory are written
+/// relative to it even without `--relative-paths`
+fn get_artifact_location(path: &Path, current_dir: &Path) -> SarifArtifactLocation {
+    let relative_path = if path.is_absolute() {
+        path.strip_prefix(current_dir).ok()
+    } else {
+        Some(path)
+    };
+    return match relative_path {
+        Some(relative_path) => SarifArtifactLocation {
+            uri: get_uri_path(relative_path),
+            uri_base_id: Some(SOURCE_ROOT_BASE_ID),
+        },
+        None => SarifArtifactLocation {
+            uri: format!("file:///{}", get_uri_path(path)),
+            uri_base_id: None,
+        },
+    };
+}
+
+fn get_location(
+    path: &Path,
+    position: Option<(u32, u32)>,
+    message: Option<String>,
+    current_dir: &Path,
+) -> SarifLocation {
+    return SarifLocation {
+        physical_location: SarifPhysicalLocation {
+            artifact_location: get_artifact_location(path, current_dir),
+            region: position.map(|(line, column)| SarifRegion {
+                start_line: line,
+                start_column: column,
+            }),
+        },
+        message: message.map(|text| SarifMessage { text }),
+    };
+}
+
+/// Writes the errors and the findings among the results as a SARIF 2.1.0 log with a single run.
+/// Each kind of error or finding is a rule - boundary rules from `check-rules` are `rules/<name>`.
+pub fn write_sarif(
+    writer: &mut impl Write,
+    records: &[OutputRecord],
+    errors: &[OutputError],
+) -> io::Result<()> {
+    let current_dir = std::env::current_dir()?;
+
+    let mut rules: Vec<SarifRule> = vec![];
+    let mut rule_indexes: BTreeMap<String, usize> = BTreeMap::new();
+    let mut get_rule_index = |id: &str, description: &str| {
+        return *rule_indexes.entry(id.to_string()).or_insert_with(|| {
+            rules.push(SarifRule {
+                id: id.to_string(),
+                short_description: SarifMessage {
+                    text: description.to_string(),
+                },
+            });
+            return rules.len() - 1;
+        });
+    };
+
+    let mut results = vec![];
+    let mut notifications = vec![];
+    for error in errors {
+        let (Some(path), Some(rule)) = (&error.path, error.rule) else {
+            notifications.push(SarifNotification {
+                level: "warning",
+                message: SarifMessage {
+                    text: error.message.clone(),
+                },
+            });
+            continue;
+        };
+        results.push(SarifResult {
+            rule_id: rule.id().to_string(),
+            rule_index: get_rule_index(rule.id(), rule.description()),
+            level: "error",
+            message: SarifMessage {
+                text: error.message.clone(),
+            },
+            locations: vec![get_location(
+                path,
+                error.line.zip(error.column),
+                None,
+                &current_dir,
+            )],
+            related_locations: vec![],
+        });
+    }
+    for finding in records.iter().filter_map(get_finding) {
+        results.push(SarifResult {
+            rule_index: get_rule_index(&finding.rule_id, &finding.description),
+            rule_id: finding.rule_id,
+            level: finding.level,
+            message: SarifMessage {
+                text: finding.message,
+            },
+            locations: vec![get_location(
+                finding.path,
+                finding.position,
+                None,
+                &current_dir,
+            )],
+            related_locations: finding
+                .related
+                .into_iter()
+                .map(|(path, message)| get_location(path, None, Some(message), &current_dir))
+                .collect(),
+        });
+    }
+
+    let log = SarifLog {
+        schema: SARIF_SCHEMA,
+        version: SARIF_VERSION,
+        runs: vec![SarifRun {
+            tool: SarifTool {
+                driver: SarifDriver {
+                    name: env!("CARGO_PKG_NAME"),
+                    version: env!("CARGO_PKG_VERSION"),
+                    rules,
+                },
+            },
+            original_uri_base_ids: BTreeMap::from([(
+                SOURCE_ROOT_BASE_ID,
+                SarifArtifactLocation {
+                    uri: format!("file:///{}/", get_uri_path(&current_dir)),
+                    uri_base_id: None,
+                },
+            )]),
+            invocations: vec![SarifInvocation {
+                execution_successful: true,
+                tool_execution_notifications: notifications,
+            }],
+            results,
+        }],
+    };
+    serde_json::to_writer_pretty(&mut *writer, &log)?;
+    return writeln!(writer);
+}
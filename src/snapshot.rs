@@ -0,0 +1,163 @@
+use petgraph::{graph::NodeIndex, visit::EdgeRef};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    module::{DependencyKind, EdgeWeight, ModuleGraph, ModuleId, ModuleKind},
+};
+
+// bump this whenever the format changes in a way that older snapshots can't be read
+// 2: file paths are relative to the repository root rather than the current directory
+const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotModule {
+    /// Files are relative to the root of the git repository (or the directory the snapshot was taken from outside of
+    /// one), so that snapshots taken from different checkouts are comparable. Node modules are the package name.
+    pub path: String,
+    pub kind: ModuleKind,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotEdge {
+    /// Index into `modules`
+    pub from: usize,
+    /// Index into `modules`
+    pub to: usize,
+    pub kind: DependencyKind,
+    pub is_type_only: bool,
+}
+
+/// A stable, path-keyed representation of the module graph which can be saved and compared against another graph.
+/// Modules are sorted by path and each pair of modules has at most one edge, so the same code always produces the same
+/// snapshot.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphSnapshot {
+    pub version: u32,
+    pub modules: Vec<SnapshotModule>,
+    pub edges: Vec<SnapshotEdge>,
+}
+
+impl GraphSnapshot {
+    /// Creates a snapshot with file paths relative to `root`
+    pub fn new<'a>(graph: &'a DependencyGraph, root: &Path) -> Result<Self, &'a str> {
+        let module_graph = graph.get_graph()?;
+
+        let mut modules = graph
+            .get_module_ids()
+            .map(|module_id| {
+                let kind = graph.get_module_kind(module_id);
+                let path = graph.get_path_for_module_id(module_id);
+                let path = match kind {
+                    ModuleKind::File => path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+                    ModuleKind::NodeModule => path,
+                };
+                return (
+                    module_id,
+                    SnapshotModule {
+                        path: path.to_string_lossy().to_string(),
+                        kind,
+                    },
+                );
+            })
+            .collect::<Vec<_>>();
+        modules.sort_by(|(_, a), (_, b)| a.path.cmp(&b.path));
+
+        let mut module_id_to_index: HashMap<ModuleId, usize> = HashMap::new();
+        for (index, (module_id, _)) in modules.iter().enumerate() {
+            module_id_to_index.insert(*module_id, index);
+        }
+
+        // parallel edges are merged - the pair is only type-only if every import between them is type-only, and only
+        // a wildcard if every import between them is a wildcard
+        let mut edges: HashMap<(usize, usize), (DependencyKind, bool)> = HashMap::new();
+        for edge in module_graph.edge_references() {
+            let key = (
+                module_id_to_index[&module_graph[edge.source()]],
+                module_id_to_index[&module_graph[edge.target()]],
+            );
+            let weight = edge.weight();
+            edges
+                .entry(key)
+                .and_modify(|(kind, is_type_only)| {
+                    if weight.kind == DependencyKind::Static {
+                        *kind = DependencyKind::Static;
+                    }
+                    *is_type_only &= weight.is_type_only;
+                })
+                .or_insert((weight.kind, weight.is_type_only));
+        }
+        let mut edges = edges
+            .into_iter()
+            .map(|((from, to), (kind, is_type_only))| SnapshotEdge {
+                from,
+                to,
+                kind,
+                is_type_only,
+            })
+            .collect::<Vec<_>>();
+        edges.sort_by_key(|edge| (edge.from, edge.to));
+
+        return Ok(Self {
+            version: SNAPSHOT_VERSION,
+            modules: modules.into_iter().map(|(_, module)| module).collect(),
+            edges,
+        });
+    }
+
+    pub fn read(path: &Path) -> Self {
+        let file = File::open(path)
+            .unwrap_or_else(|_| panic!("Unable to open snapshot {}", path.display()));
+        let snapshot: Self = serde_json::from_reader(BufReader::new(file))
+            .unwrap_or_else(|e| panic!("Unable to parse snapshot {}: {}", path.display(), e));
+        if snapshot.version != SNAPSHOT_VERSION {
+            panic!(
+                "Snapshot {} has version {} but this version of athena reads version {}",
+                path.display(),
+                snapshot.version,
+                SNAPSHOT_VERSION
+            );
+        }
+
+        return snapshot;
+    }
+
+    pub fn write(&self, path: &Path) {
+        let file = File::create(path)
+            .unwrap_or_else(|_| panic!("Unable to create snapshot {}", path.display()));
+        serde_json::to_writer_pretty(BufWriter::new(file), self)
+            .unwrap_or_else(|e| panic!("Unable to write snapshot {}: {}", path.display(), e));
+    }
+
+    /// Builds a module graph where the module ID of each node is its index in `modules`, so the graph analyses can be
+    /// run against a snapshot
+    pub fn to_module_graph(&self) -> ModuleGraph {
+        let mut graph = ModuleGraph::with_capacity(self.modules.len(), self.edges.len());
+        for index in 0..self.modules.len() {
+            graph.add_node(ModuleId::from(index));
+        }
+        for edge in self.edges.iter() {
+            graph.add_edge(
+                NodeIndex::new(edge.from),
+                NodeIndex::new(edge.to),
+                EdgeWeight {
+                    kind: edge.kind,
+                    // snapshots don't keep the parsed modules, so there is no dependency to point at
+                    dependency_index: 0,
+                    is_type_only: edge.is_type_only,
+                },
+            );
+        }
+
+        return graph;
+    }
+}
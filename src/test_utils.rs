@@ -0,0 +1,99 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use swc_common::{sync::Lrc, SourceMap};
+
+use crate::{
+    call_pattern::CallPattern,
+    dependency_graph::DependencyGraph,
+    diagnostic::Diagnostic,
+    file_system::get_files,
+    import_visitor::{ImportVisitor, ParsedModule},
+    output::{Output, OutputFormat},
+    package_json::find_package_jsons,
+    parser::parse_file,
+    tsconfig::TSConfig,
+};
+
+static NEXT_PROJECT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A project written to a temporary directory for a test, which is removed when dropped
+pub struct TestProject {
+    pub root: PathBuf,
+}
+impl TestProject {
+    /// Writes each `(relative path, contents)` pair, creating the directories as needed
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "athena-test-{}-{}",
+            std::process::id(),
+            NEXT_PROJECT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&root).expect("Unable to create the test project");
+        let project = Self {
+            root: root
+                .canonicalize()
+                .expect("Unable to canonicalize the test project"),
+        };
+        for (path, contents) in files {
+            project.write(path, contents);
+        }
+        return project;
+    }
+
+    pub fn path(&self, path: &str) -> PathBuf {
+        return self.root.join(path);
+    }
+
+    pub fn write(&self, path: &str, contents: &str) {
+        let path = self.path(path);
+        fs::create_dir_all(path.parent().expect("Path should not be the root"))
+            .expect("Unable to create the test directory");
+        fs::write(&path, contents).expect("Unable to write the test file");
+    }
+
+    pub fn build_graph(&self) -> DependencyGraph {
+        return self.build_graph_with_errors(&[]).0;
+    }
+
+    /// Parses and resolves the project the same way the CLI does, without a tsconfig or a cache
+    pub fn build_graph_with_errors(
+        &self,
+        call_patterns: &[CallPattern],
+    ) -> (DependencyGraph, HashMap<PathBuf, Vec<Diagnostic>>) {
+        let output = Output::new(OutputFormat::Text, None);
+        let search_paths = vec![self.root.clone()];
+        let files = get_files(&search_paths, &output);
+
+        let parsed_modules = files
+            .iter()
+            .map(|file| (file, parse_test_file(file, call_patterns)))
+            .collect::<Vec<_>>();
+        let mut graph = DependencyGraph::new(
+            &files,
+            &TSConfig::default(),
+            find_package_jsons(&search_paths, &output),
+        );
+        let (errors, _) = graph.resolve_imports(parsed_modules, HashMap::new());
+
+        return (graph, errors.unwrap_or_default());
+    }
+}
+impl Drop for TestProject {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+pub fn parse_test_file(path: &Path, call_patterns: &[CallPattern]) -> ParsedModule {
+    let source_map: Lrc<SourceMap> = Default::default();
+    let mut visitor = ImportVisitor::new(source_map.clone(), call_patterns);
+    parse_file(&source_map, path, &mut visitor);
+    return ParsedModule {
+        dependencies: visitor.dependencies,
+        exports: visitor.exports,
+    };
+}
@@ -0,0 +1,265 @@
+use globset::GlobSet;
+use petgraph::{visit::EdgeRef, Direction};
+use std::{
+    collections::{HashSet, VecDeque},
+    path::PathBuf,
+};
+
+use crate::{
+    dependency_graph::DependencyGraph,
+    import_visitor::{ExportBinding, ImportBinding, SourceLocation, DEFAULT_EXPORT_NAME},
+    module::{ModuleId, ModuleKind},
+};
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Usage {
+    /// A single export was used - eg `import { foo } from 'mod'`
+    Name(String),
+    /// Every export was used - eg `import * as mod from 'mod'`
+    All,
+    /// Every export was used via an `export * from 'mod'`, which never re-exports the default export
+    AllExceptDefault,
+}
+
+pub struct UnusedExport {
+    pub name: String,
+    pub location: SourceLocation,
+}
+
+/// Finds the exports of every file which are not imported by any other file.
+///
+/// Usages are followed through re-exports, so an export which is only used via a barrel file is still considered used,
+/// and an export which is re-exported by a barrel file but never used is reported in both files.
+/// Exports from files matching the entry points are considered public API and are always used.
+pub fn get_unused_exports<'a>(
+    graph: &'a DependencyGraph,
+    entry_points: &GlobSet,
+) -> Result<Vec<(PathBuf, Vec<UnusedExport>)>, &'a str> {
+    let module_graph = graph.get_graph()?;
+
+    // tracks which exports have been used for each module, indexed by the export's index in `ParsedModule::exports`
+    let mut used_exports: Vec<Vec<bool>> = graph
+        .get_module_ids()
+        .map(|module_id| match graph.get_parsed_module(module_id) {
+            Some(parsed_module) => vec![false; parsed_module.exports.len()],
+            None => vec![],
+        })
+        .collect();
+
+    let mut queue: VecDeque<(ModuleId, Usage)> = VecDeque::new();
+    for module_id in graph.get_module_ids() {
+        let parsed_module = match graph.get_parsed_module(module_id) {
+            Some(parsed_module) => parsed_module,
+            None => continue,
+        };
+
+        if entry_points.is_match(graph.get_path_for_module_id(module_id)) {
+            queue.push_back((module_id, Usage::All));
+        }
+
+        for edge in module_graph
+            .edges_directed(graph.get_node_for_module_id(module_id), Direction::Outgoing)
+        {
+            let dependency = &parsed_module.dependencies[edge.weight().dependency_index as usize];
+            let target_id = module_graph[edge.target()];
+            for binding in dependency.imports.iter() {
+                queue.push_back((
+                    target_id,
+                    match binding {
+                        ImportBinding::Default { .. } => {
+                            Usage::Name(DEFAULT_EXPORT_NAME.to_string())
+                        }
+                        ImportBinding::Named { imported, .. } => Usage::Name(imported.clone()),
+                        ImportBinding::Namespace { .. } => Usage::All,
+                    },
+                ));
+            }
+        }
+    }
+
+    // propagate the usages through the re-exports
+    let mut seen: HashSet<(usize, Usage)> = HashSet::new();
+    while let Some((module_id, usage)) = queue.pop_front() {
+        if !seen.insert((module_id.into(), usage.clone())) {
+            continue;
+        }
+        let parsed_module = match graph.get_parsed_module(module_id) {
+            Some(parsed_module) => parsed_module,
+            None => continue,
+        };
+
+        let get_dependency_targets = |dependency_index: usize| {
+            return module_graph
+                .edges_directed(graph.get_node_for_module_id(module_id), Direction::Outgoing)
+                .filter(move |edge| edge.weight().dependency_index as usize == dependency_index)
+                .map(|edge| module_graph[edge.target()]);
+        };
+
+        let mut has_explicit_export = false;
+        for (export_index, export) in parsed_module.exports.iter().enumerate() {
+            match export {
+                ExportBinding::Local { name, .. } => {
+                    if is_name_used(&usage, name) {
+                        has_explicit_export = true;
+                        used_exports[usize::from(module_id)][export_index] = true;
+                    }
+                }
+                ExportBinding::ReExport {
+                    name,
+                    imported,
+                    dependency_index,
+                    ..
+                } => {
+                    if is_name_used(&usage, name) {
+                        has_explicit_export = true;
+                        used_exports[usize::from(module_id)][export_index] = true;
+                        for target_id in get_dependency_targets(*dependency_index) {
+                            queue.push_back((
+                                target_id,
+                                match imported {
+                                    Some(imported) => Usage::Name(imported.clone()),
+                                    None => Usage::All,
+                                },
+                            ));
+                        }
+                    }
+                }
+                ExportBinding::ReExportAll { .. } => {}
+            }
+        }
+
+        // `export *` never re-exports the default, and explicit exports take precedence over it
+        let star_usage = match &usage {
+            Usage::Name(name) if name == DEFAULT_EXPORT_NAME || has_explicit_export => None,
+            Usage::Name(name) => Some(Usage::Name(name.clone())),
+            Usage::All | Usage::AllExceptDefault => Some(Usage::AllExceptDefault),
+        };
+        if let Some(star_usage) = star_usage {
+            for export in parsed_module.exports.iter() {
+                if let ExportBinding::ReExportAll {
+                    dependency_index, ..
+                } = export
+                {
+                    for target_id in get_dependency_targets(*dependency_index) {
+                        queue.push_back((target_id, star_usage.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut unused_exports = graph
+        .get_module_ids()
+        .filter(|module_id| graph.get_module_kind(*module_id) == ModuleKind::File)
+        .filter_map(|module_id| {
+            let parsed_module = graph.get_parsed_module(module_id)?;
+            let unused = parsed_module
+                .exports
+                .iter()
+                .zip(used_exports[usize::from(module_id)].iter())
+                .filter(|(_, is_used)| !**is_used)
+                .filter_map(|(export, _)| match export {
+                    ExportBinding::Local { name, location }
+                    | ExportBinding::ReExport { name, location, .. } => Some(UnusedExport {
+                        name: name.clone(),
+                        location: *location,
+                    }),
+                    // there's no name to report for an `export *`
+                    ExportBinding::ReExportAll { .. } => None,
+                })
+                .collect::<Vec<_>>();
+            if unused.is_empty() {
+                return None;
+            }
+            return Some((graph.get_path_for_module_id(module_id), unused));
+        })
+        .collect::<Vec<_>>();
+    unused_exports.sort_by(|a, b| a.0.cmp(&b.0));
+
+    return Ok(unused_exports);
+}
+
+fn is_name_used(usage: &Usage, name: &str) -> bool {
+    return match usage {
+        Usage::Name(used_name) => used_name == name,
+        Usage::All => true,
+        Usage::AllExceptDefault => name != DEFAULT_EXPORT_NAME,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        call_pattern::jest_call_patterns, file_system::build_glob_set_relative_to,
+        test_utils::TestProject,
+    };
+
+    fn get_unused_export_names(project: &TestProject) -> Vec<(PathBuf, Vec<String>)> {
+        let (graph, _) = project.build_graph_with_errors(&jest_call_patterns());
+        let entry_points =
+            build_glob_set_relative_to(&["index.ts".to_string()], &project.root).unwrap();
+        let mut unused_exports = get_unused_exports(&graph, &entry_points)
+            .unwrap()
+            .into_iter()
+            .map(|(path, exports)| {
+                return (
+                    path,
+                    exports.into_iter().map(|export| export.name).collect(),
+                );
+            })
+            .collect::<Vec<_>>();
+        unused_exports.sort();
+        return unused_exports;
+    }
+
+    #[test]
+    fn usages_are_followed_through_re_exports() {
+        let project = TestProject::new(&[
+            (
+                "index.ts",
+                // the entry point's exports are its public API
+                "import { a, c } from './barrel';\nexport const api = 1;",
+            ),
+            (
+                "barrel.ts",
+                "export * from './a';\nexport { c, d } from './c';",
+            ),
+            (
+                "a.ts",
+                "export const a = 1;\nexport const b = 2;\nexport default 3;",
+            ),
+            ("c.ts", "export const c = 1;\nexport const d = 2;"),
+        ]);
+
+        assert_eq!(
+            get_unused_export_names(&project),
+            vec![
+                (
+                    project.path("a.ts"),
+                    vec!["b".to_string(), "default".to_string()]
+                ),
+                (project.path("barrel.ts"), vec!["d".to_string()]),
+                (project.path("c.ts"), vec!["d".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_calls_which_return_the_module_use_every_export() {
+        let project = TestProject::new(&[
+            (
+                "index.ts",
+                "const a = require('./a');\nimport('./b');\njest.mock('./c');",
+            ),
+            ("a.ts", "export const a = 1;"),
+            ("b.ts", "export const b = 1;"),
+            ("c.ts", "export const c = 1;"),
+        ]);
+
+        assert_eq!(
+            get_unused_export_names(&project),
+            vec![(project.path("c.ts"), vec!["c".to_string()])]
+        );
+    }
+}